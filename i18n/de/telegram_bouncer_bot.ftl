@@ -1,13 +1,123 @@
-privacy-policy = Wir speichern deine user ID und Anzeigenamen für die Dauer der Prüfung, nach dem wir diese wieder löschen. Es werden keine weiterne Daten erhoben.
+privacy-policy = Wir speichern deine user ID, deinen Anzeigenamen und deine Antworten, um deine Beitrittsanfrage zu prüfen. Um unsere Moderationsentscheidungen nachvollziehen zu können, werden diese Daten nach der Prüfung in unserem Moderationsverlauf aufbewahrt. Es werden keine weiteren Daten erhoben.
 invalid-state = Die Nachricht kann nicht verarbeitet werden. Gebe /help ein, um die Verwendung anzuzeigen.
 present-member = Du bist bereits Teilnehmer im Chat.
+group-unknown = Ich kenne den Chat nicht, dem du beitreten möchtest. Bitte verwende den Link, den du vom Chat erhalten hast.
+application-abandoned = Deine unvollständige Beitrittsanfrage für einen anderen Chat wurde abgebrochen, da du dich nur bei einem Chat gleichzeitig bewerben kannst. Du kannst sie erneut stellen, sobald du hier fertig bist.
 reason-prompt = Sage mir bitte in wenigen Worten, warum du an diesem Chat teilnehmen möchtest.
 reason-missing = Bitte sende mir deinen Grund für den Beitritt.
 reason-received = Vielen Dank, unsere Moderatoren werden deine Beitrittsanfrage prüfen.
 cancelling-join-request = Abbrechen der Beitrittsanfrage.
 request-approved = Deine Anfrage wurde genehmigt. Du kannst jetzt am Chat teilnehmen, indem du diesem Link folgst: {$link}
 request-denied = Deine Anfrage wurde abgelehnt. Wenn du glaubst, dass dies ein Fehler war, kannst du /start erneut aufrufen.
+request-expired = Deine Anfrage wurde nicht rechtzeitig geprüft und ist abgelaufen. Du kannst /start erneut aufrufen, um eine neue Anfrage zu stellen.
+decision-undone = Bitte ignoriere unsere vorherige Nachricht, sie wurde versehentlich gesendet. Deine Anfrage wird erneut geprüft.
 under-review = Dein Antrag wird noch geprüft.
 blocked = Weitere Interaktionen mit diesem Bot wurden für dich gesperrt.
 unblocked = Deine Sperre wurde aufgehoben und du kannst erneut /start aufrufen.
 contact-requested = Wir benötigen weitere Informationen, bitte kontaktiere {$moderator}\.
+join-request-prompt = Wir haben deine Beitrittsanfrage erhalten. Sage mir bitte in wenigen Worten, warum du an diesem Chat teilnehmen möchtest.
+join-request-approved = Deine Anfrage wurde genehmigt, willkommen im Chat!
+questions-intro = Bitte beantworte die folgenden Fragen, damit unsere Moderatoren deine Anfrage prüfen können.
+join-request-questions-intro = Wir haben deine Beitrittsanfrage erhalten. Bitte beantworte die folgenden Fragen, damit unsere Moderatoren sie prüfen können.
+answer-missing = Bitte beantworte die Frage mit einer Textnachricht.
+question-found-us = Wie hast du uns gefunden?
+question-rules = Bist du damit einverstanden, die Regeln des Chats zu befolgen?
+rules-intro = Bevor du den Beitritt anfragen kannst, lies bitte die Regeln des Chats:
+join-request-rules-intro = Wir haben deine Beitrittsanfrage erhalten. Bevor wir fortfahren, lies bitte die Regeln des Chats:
+rules-agree = Ich stimme zu
+rules-pending = Bitte lies die obigen Regeln und tippe auf "Ich stimme zu", um fortzufahren.
+request-denied-reason = Deine Anfrage wurde aus folgendem Grund abgelehnt: {$reason}
+
+    Wenn du glaubst, dass dies ein Fehler war, kannst du /start erneut aufrufen.
+deny-reason-incomplete = Deine Antworten haben uns nicht genug über dich verraten.
+deny-reason-spam = Deine Anfrage sieht nach Spam aus.
+deny-reason-off-topic = Dieser Chat scheint nicht der richtige Ort für dich zu sein.
+
+## Nachrichten im Moderatoren-Chat, die MODERATOR_LOCALE verwenden
+
+moderator-review-reason = möchte aus folgendem Grund beitreten:
+moderator-review-answers = möchte beitreten und hat die folgenden Fragen beantwortet:
+moderator-rules-accepted = Regeln akzeptiert am {$time}
+moderator-tag-banned = GESPERRT
+moderator-tag-listed = GELISTET VON {$source}
+moderator-button-approve = Genehmigen
+moderator-button-unban-approve = Entsperren & genehmigen
+moderator-button-deny = Ablehnen
+moderator-button-deny-reason = Ablehnen: {$reason}
+moderator-button-block = Blockieren
+moderator-button-request-contact = Kontakt anfordern
+moderator-button-block-report = Blockieren & als Betrüger melden
+moderator-button-unblock = Blockierung aufheben
+moderator-button-undo = Rückgängig
+moderator-action-approve = Genehmigt
+moderator-action-unban-approve = Entsperrt & genehmigt
+moderator-action-deny = Abgelehnt
+moderator-action-block = Blockiert
+moderator-action-block-report = Blockiert & als Betrüger gemeldet
+moderator-action-unblock = Blockierung aufgehoben
+moderator-action-request-contact = Kontakt angefordert
+moderator-action-undo = Rückgängig gemacht
+moderator-outcome-expired = Abgelaufen
+moderator-outcome-unknown = Unbekannte Aktion
+moderator-decided-by = {$action} von {$reviewers}
+moderator-decision-reason = Grund: {$reason}
+moderator-bot-blocked = Der Nutzer hat diesen Bot blockiert
+moderator-user-deactivated = Das Konto des Nutzers ist deaktiviert
+moderator-votes = Stimmen:
+moderator-approvals = Genehmigungen ({$count}/{$quorum}): {$voters}
+moderator-denials = Ablehnungen ({$count}/{$quorum}): {$voters}
+moderator-vote-recorded = Stimme erfasst
+moderator-votes-lost = Die Stimmen zu dieser Beitrittsanfrage sind verloren gegangen, daher kann sie nicht mehr entschieden werden
+moderator-automatic-policy = automatische Regel
+moderator-automatic-deny = {$user} wurde automatisch abgelehnt, da der Nutzer von {$sources} gelistet wird
+moderator-automatic-block = {$user} wurde automatisch blockiert, da der Nutzer von {$sources} gelistet wird
+moderator-automatic-ban = {$user} wurde automatisch gesperrt, da der Nutzer von {$sources} gelistet wird
+moderator-joined = Beigetreten am {$time}
+moderator-joined-by-other = Einladungslink verwendet von {$user} am {$time}
+moderator-expired = Ohne Entscheidung abgelaufen
+moderator-pending-for = Diese Beitrittsanfrage ist seit {$age} offen
+moderator-pending-for-expiring = Diese Beitrittsanfrage ist seit {$age} offen und läuft in {$remaining} ab
+moderator-hours = { $hours ->
+    [0] weniger als einer Stunde
+    [one] 1 Stunde
+   *[other] {$hours} Stunden
+}
+moderator-buttons-outdated = Diese Schaltflächen wurden von einer älteren Version des Bots erstellt und funktionieren nicht mehr. Bitte verwende stattdessen die Moderatoren-Befehle.
+moderator-buttons-invalid = Diese Schaltflächen sind ungültig. Bitte verwende stattdessen die Moderatoren-Befehle.
+moderator-request-unknown = Diese Beitrittsanfrage ist unbekannt. Bitte verwende stattdessen die Moderatoren-Befehle.
+moderator-already-expired = Diese Beitrittsanfrage ist bereits abgelaufen
+moderator-already-decided = Über diese Beitrittsanfrage wurde bereits entschieden: {$action} von {$reviewers}
+moderator-undo-expired = Diese Entscheidung kann nicht mehr rückgängig gemacht werden
+moderator-undo-joined = Die Person ist bereits beigetreten, daher kann die Genehmigung nicht rückgängig gemacht werden
+moderator-pending-empty = Es gibt keine offenen Beitrittsanfragen
+moderator-pending = Offene Beitrittsanfragen:
+moderator-pending-entry = {$time} – {$user}, { $votes ->
+    [one] 1 Stimme
+   *[other] {$votes} Stimmen
+}
+moderator-stats = Beitrittsanfragen: {$requests} ({$open} offen)
+moderator-history-usage = Verwendung: /history <user id oder @username>
+moderator-history-empty = Keine Beitrittsanfragen von {$target} gefunden
+moderator-history = Beitrittsanfragen von {$target}:
+moderator-history-pending = Offen
+moderator-history-expired = Abgelaufen am {$time} ({$result})
+moderator-history-decision = {$action} von {$reviewers} am {$time} ({$result})
+moderator-user-id-missing = Bitte gib eine user ID an
+moderator-deny-usage = Bitte antworte mit /deny <grund> auf eine offene Prüfung
+moderator-deny-reason-missing = Bitte gib einen Grund an
+moderator-review-not-pending = Diese Prüfung ist nicht mehr offen
+moderator-request-missing = Es gibt keine offene Beitrittsanfrage von {$user_id}
+moderator-user-blocked = {$user_id} ist blockiert, verwende zuerst /unblock
+moderator-user-not-blocked = {$user_id} ist nicht blockiert
+moderator-report-usage = Verwendung: /report <user id> [grund]
+moderator-banlist-added = {$user_id} wurde zur Sperrliste hinzugefügt
+moderator-banlist-updated = Der Eintrag von {$user_id} in der Sperrliste wurde aktualisiert
+moderator-banlist-removed = {$user_id} wurde von der Sperrliste entfernt
+moderator-banlist-missing = {$user_id} steht nicht auf der Sperrliste
+moderator-banlist-failed = Die Sperrliste konnte nicht gespeichert werden und wurde daher nicht geändert: {$error}
+moderator-import-usage = Bitte antworte mit /importbanlist auf eine Sperrlisten-Datei
+moderator-import-done = {$count} neue Einträge in die Sperrliste importiert
+moderator-import-invalid = Die Datei ist keine gültige Sperrliste: {$error}
+moderator-reloaded = Die Konfiguration wurde neu geladen
+moderator-reloaded-restart = Die Konfiguration wurde neu geladen. Änderungen an {$settings} werden erst nach einem Neustart wirksam.
+moderator-reload-failed = Die Konfiguration konnte nicht neu geladen werden: {$error}
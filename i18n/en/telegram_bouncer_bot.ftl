@@ -1,13 +1,123 @@
-privacy-policy = We store your user ID and display name for the duration of the review, after which they will be deleted. No other data are collected.
+privacy-policy = We store your user ID, display name and answers to review your join request. To keep a record of our moderation decisions, these data are retained in our moderation history after the review. No other data are collected.
 invalid-state = Unable to handle the message. Type /help to see the usage.
 present-member = You are already a member of the chat.
+group-unknown = I don't know the chat you are trying to join. Please use the link provided by the chat.
+application-abandoned = Your unfinished request to join another chat was cancelled, as you can only apply to one chat at a time. You can request to join it again once you are done here.
 reason-prompt = Please tell me in a few words why you want to join this chat.
 reason-missing = Please send me your reason to join.
 reason-received = Thank you, our moderators will review your join request.
 cancelling-join-request = Cancelling the join request.
 request-approved = Your request has been approved. You can join the the chat now by following this link: {$link}
 request-denied = Your request has been denied. If you think this was an error, you can /start again.
+request-expired = Your request was not reviewed in time and has expired. You can /start again to submit a new one.
+decision-undone = Please disregard our previous message, as it was sent by mistake. Your request is being reviewed again.
 under-review = Your request is still under review.
 blocked = You have been blocked from further interactions with this bot.
 unblocked = You have been unblocked and can /start again.
 contact-requested = We require more information, please contact {$moderator}\.
+join-request-prompt = We received your request to join the chat. Please tell me in a few words why you want to join.
+join-request-approved = Your request has been approved, welcome to the chat!
+questions-intro = Please answer the following questions so our moderators can review your request.
+join-request-questions-intro = We received your request to join the chat. Please answer the following questions so our moderators can review it.
+answer-missing = Please answer the question with a text message.
+question-found-us = How did you find us?
+question-rules = Do you agree to follow the rules of the chat?
+rules-intro = Before you can request to join, please read the rules of the chat:
+join-request-rules-intro = We received your request to join the chat. Before we continue, please read the rules of the chat:
+rules-agree = I agree
+rules-pending = Please read the rules above and tap "I agree" to continue.
+request-denied-reason = Your request has been denied for the following reason: {$reason}
+
+    If you think this was an error, you can /start again.
+deny-reason-incomplete = Your answers did not tell us enough about you.
+deny-reason-spam = Your request looks like spam.
+deny-reason-off-topic = This chat does not seem to be the right place for you.
+
+## Messages in the moderator chat, which use MODERATOR_LOCALE
+
+moderator-review-reason = would like to join for the following reason:
+moderator-review-answers = would like to join and answered the following questions:
+moderator-rules-accepted = Accepted the rules at {$time}
+moderator-tag-banned = BANNED
+moderator-tag-listed = LISTED BY {$source}
+moderator-button-approve = Approve
+moderator-button-unban-approve = Unban & Approve
+moderator-button-deny = Deny
+moderator-button-deny-reason = Deny: {$reason}
+moderator-button-block = Block
+moderator-button-request-contact = Request contact
+moderator-button-block-report = Block & report as scammer
+moderator-button-unblock = Unblock
+moderator-button-undo = Undo
+moderator-action-approve = Approved
+moderator-action-unban-approve = Unbanned & Approved
+moderator-action-deny = Denied
+moderator-action-block = Blocked
+moderator-action-block-report = Blocked & reported as scammer
+moderator-action-unblock = Unblocked
+moderator-action-request-contact = Contact requested
+moderator-action-undo = Undone
+moderator-outcome-expired = Expired
+moderator-outcome-unknown = Unknown action
+moderator-decided-by = {$action} by {$reviewers}
+moderator-decision-reason = Reason: {$reason}
+moderator-bot-blocked = User has blocked this bot
+moderator-user-deactivated = User has a deactivated account
+moderator-votes = Votes:
+moderator-approvals = Approvals ({$count}/{$quorum}): {$voters}
+moderator-denials = Denials ({$count}/{$quorum}): {$voters}
+moderator-vote-recorded = Vote recorded
+moderator-votes-lost = The votes on this join request were lost, so it can no longer be decided
+moderator-automatic-policy = automatic policy
+moderator-automatic-deny = {$user} was denied automatically, as they are listed by {$sources}
+moderator-automatic-block = {$user} was blocked automatically, as they are listed by {$sources}
+moderator-automatic-ban = {$user} was banned automatically, as they are listed by {$sources}
+moderator-joined = Joined at {$time}
+moderator-joined-by-other = Invite link used by {$user} at {$time}
+moderator-expired = Expired without a decision
+moderator-pending-for = This join request has been pending for {$age}
+moderator-pending-for-expiring = This join request has been pending for {$age} and expires in {$remaining}
+moderator-hours = { $hours ->
+    [0] less than an hour
+    [one] 1 hour
+   *[other] {$hours} hours
+}
+moderator-buttons-outdated = These buttons were created by an older version of the bot and no longer work. Please use the moderator commands instead.
+moderator-buttons-invalid = These buttons are invalid. Please use the moderator commands instead.
+moderator-request-unknown = This join request is unknown. Please use the moderator commands instead.
+moderator-already-expired = This join request has already expired
+moderator-already-decided = This join request was already decided: {$action} by {$reviewers}
+moderator-undo-expired = This decision can no longer be undone
+moderator-undo-joined = The applicant has already joined, so the approval cannot be undone
+moderator-pending-empty = There are no open join requests
+moderator-pending = Open join requests:
+moderator-pending-entry = {$time} – {$user}, { $votes ->
+    [one] 1 vote
+   *[other] {$votes} votes
+}
+moderator-stats = Join requests: {$requests} ({$open} open)
+moderator-history-usage = Usage: /history <user id or @username>
+moderator-history-empty = No join requests found for {$target}
+moderator-history = Join requests of {$target}:
+moderator-history-pending = Pending
+moderator-history-expired = Expired at {$time} ({$result})
+moderator-history-decision = {$action} by {$reviewers} at {$time} ({$result})
+moderator-user-id-missing = Please specify a user ID
+moderator-deny-usage = Please reply to a pending review with /deny <reason>
+moderator-deny-reason-missing = Please specify a reason
+moderator-review-not-pending = This review is no longer pending
+moderator-request-missing = There is no pending join request from {$user_id}
+moderator-user-blocked = {$user_id} is blocked, use /unblock first
+moderator-user-not-blocked = {$user_id} is not blocked
+moderator-report-usage = Usage: /report <user id> [reason]
+moderator-banlist-added = Added {$user_id} to the banlist
+moderator-banlist-updated = Updated the banlist entry of {$user_id}
+moderator-banlist-removed = Removed {$user_id} from the banlist
+moderator-banlist-missing = {$user_id} is not on the banlist
+moderator-banlist-failed = The banlist could not be saved, so it was not changed: {$error}
+moderator-import-usage = Please reply to a banlist file with /importbanlist
+moderator-import-done = Imported {$count} new banlist entries
+moderator-import-invalid = The file is not a valid banlist: {$error}
+moderator-reloaded = Reloaded the configuration
+moderator-reloaded-restart = Reloaded the configuration. Changes to {$settings} only take effect after a restart.
+moderator-reload-failed = Failed to reload the configuration: {$error}
@@ -1,13 +1,124 @@
-privacy-policy = Nous conservons votre User ID et votre nom d'affichage pendant la durée de l'examen, après quoi ils seront supprimés. Aucune autre donnée n'est collectée.
+privacy-policy = Nous conservons votre User ID, votre nom d'affichage et vos réponses afin d'examiner votre demande. Pour garder une trace de nos décisions de modération, ces données sont conservées dans notre historique de modération après l'examen. Aucune autre donnée n'est collectée.
 invalid-state = Impossible de traiter le message. Tapez /help pour voir l'utilisation.
 present-member = Vous êtes déjà membre dans ce tchat.
+group-unknown = Je ne connais pas le tchat que vous souhaitez rejoindre. Veuillez utiliser le lien fourni par le tchat.
+application-abandoned = Votre demande inachevée pour rejoindre un autre tchat a été annulée, car vous ne pouvez postuler qu'à un seul tchat à la fois. Vous pourrez la renouveler une fois que vous aurez terminé ici.
 reason-prompt = Veuillez me dire en quelques mots pourquoi vous voulez rejoindre ce tchat.
 reason-missing = Veuillez me dire pourquoi vous voulez rejoindre ce tchat.
 reason-received = Merci, nos modérateurs vont examiner votre demande d'inclusion.
 cancelling-join-request = Annulation de la demande d'inclusion.
 request-approved = Votre demande a été approuvée. Vous pouvez rejoindre le chat maintenant en suivant ce lien : {$link}
 request-denied = Votre demande a été refusée. Si vous pensez qu'il s'agit d'une erreur, vous pouvez recommencer.
+request-expired = Votre demande n'a pas été examinée à temps et a expiré. Vous pouvez utiliser /start à nouveau pour en envoyer une nouvelle.
+decision-undone = Veuillez ignorer notre message précédent, il a été envoyé par erreur. Votre demande est à nouveau en cours d'examen.
 under-review = Votre demande est toujours en cours d'examen.
 blocked = Vous avez été banni d'interagir avec ce bot.
 unblocked = Vous avez été débloqué et vous pouvez de nouveau utiliser /start.
 contact-requested = Nous avons besoin de plus d'informations, veuillez contacter {$moderator}\.
+join-request-prompt = Nous avons reçu votre demande pour rejoindre le tchat. Veuillez me dire en quelques mots pourquoi vous voulez rejoindre ce tchat.
+join-request-approved = Votre demande a été approuvée, bienvenue dans le tchat !
+questions-intro = Veuillez répondre aux questions suivantes afin que nos modérateurs puissent examiner votre demande.
+join-request-questions-intro = Nous avons reçu votre demande pour rejoindre le tchat. Veuillez répondre aux questions suivantes afin que nos modérateurs puissent l'examiner.
+answer-missing = Veuillez répondre à la question par un message texte.
+question-found-us = Comment nous avez-vous trouvés ?
+question-rules = Acceptez-vous de respecter les règles du tchat ?
+rules-intro = Avant de demander à rejoindre le tchat, veuillez lire ses règles :
+join-request-rules-intro = Nous avons reçu votre demande pour rejoindre le tchat. Avant de continuer, veuillez lire ses règles :
+rules-agree = J'accepte
+rules-pending = Veuillez lire les règles ci-dessus et appuyer sur « J'accepte » pour continuer.
+request-denied-reason = Votre demande a été refusée pour la raison suivante : {$reason}
+
+    Si vous pensez qu'il s'agit d'une erreur, vous pouvez recommencer.
+deny-reason-incomplete = Vos réponses ne nous en disent pas assez sur vous.
+deny-reason-spam = Votre demande ressemble à du spam.
+deny-reason-off-topic = Ce tchat ne semble pas être le bon endroit pour vous.
+
+## Messages dans le tchat des modérateurs, qui utilisent MODERATOR_LOCALE
+
+moderator-review-reason = souhaite rejoindre pour la raison suivante :
+moderator-review-answers = souhaite rejoindre et a répondu aux questions suivantes :
+moderator-rules-accepted = Règles acceptées le {$time}
+moderator-tag-banned = BANNI
+moderator-tag-listed = LISTÉ PAR {$source}
+moderator-button-approve = Approuver
+moderator-button-unban-approve = Débannir & approuver
+moderator-button-deny = Refuser
+moderator-button-deny-reason = Refuser : {$reason}
+moderator-button-block = Bloquer
+moderator-button-request-contact = Demander un contact
+moderator-button-block-report = Bloquer & signaler comme arnaqueur
+moderator-button-unblock = Débloquer
+moderator-button-undo = Annuler
+moderator-action-approve = Approuvé
+moderator-action-unban-approve = Débanni & approuvé
+moderator-action-deny = Refusé
+moderator-action-block = Bloqué
+moderator-action-block-report = Bloqué & signalé comme arnaqueur
+moderator-action-unblock = Débloqué
+moderator-action-request-contact = Contact demandé
+moderator-action-undo = Annulé
+moderator-outcome-expired = Expiré
+moderator-outcome-unknown = Action inconnue
+moderator-decided-by = {$action} par {$reviewers}
+moderator-decision-reason = Raison : {$reason}
+moderator-bot-blocked = L'utilisateur a bloqué ce bot
+moderator-user-deactivated = Le compte de l'utilisateur est désactivé
+moderator-votes = Votes :
+moderator-approvals = Approbations ({$count}/{$quorum}) : {$voters}
+moderator-denials = Refus ({$count}/{$quorum}) : {$voters}
+moderator-vote-recorded = Vote enregistré
+moderator-votes-lost = Les votes sur cette demande d'adhésion ont été perdus, elle ne peut donc plus être tranchée
+moderator-automatic-policy = règle automatique
+moderator-automatic-deny = {$user} a été refusé automatiquement, car listé par {$sources}
+moderator-automatic-block = {$user} a été bloqué automatiquement, car listé par {$sources}
+moderator-automatic-ban = {$user} a été banni automatiquement, car listé par {$sources}
+moderator-joined = A rejoint le {$time}
+moderator-joined-by-other = Lien d'invitation utilisé par {$user} le {$time}
+moderator-expired = Expiré sans décision
+moderator-pending-for = Cette demande est en attente depuis {$age}
+moderator-pending-for-expiring = Cette demande est en attente depuis {$age} et expire dans {$remaining}
+moderator-hours = { $hours ->
+    [0] moins d'une heure
+    [one] 1 heure
+   *[other] {$hours} heures
+}
+moderator-buttons-outdated = Ces boutons ont été créés par une ancienne version du bot et ne fonctionnent plus. Veuillez utiliser les commandes des modérateurs à la place.
+moderator-buttons-invalid = Ces boutons ne sont pas valides. Veuillez utiliser les commandes des modérateurs à la place.
+moderator-request-unknown = Cette demande est inconnue. Veuillez utiliser les commandes des modérateurs à la place.
+moderator-already-expired = Cette demande a déjà expiré
+moderator-already-decided = Cette demande a déjà été traitée : {$action} par {$reviewers}
+moderator-undo-expired = Cette décision ne peut plus être annulée
+moderator-undo-joined = Le demandeur a déjà rejoint, l'approbation ne peut donc pas être annulée
+moderator-pending-empty = Il n'y a aucune demande en attente
+moderator-pending = Demandes en attente :
+moderator-pending-entry = {$time} – {$user}, { $votes ->
+    [0] 0 vote
+    [one] 1 vote
+   *[other] {$votes} votes
+}
+moderator-stats = Demandes : {$requests} ({$open} en attente)
+moderator-history-usage = Utilisation : /history <user id ou @username>
+moderator-history-empty = Aucune demande trouvée pour {$target}
+moderator-history = Demandes de {$target} :
+moderator-history-pending = En attente
+moderator-history-expired = Expiré le {$time} ({$result})
+moderator-history-decision = {$action} par {$reviewers} le {$time} ({$result})
+moderator-user-id-missing = Veuillez indiquer un user ID
+moderator-deny-usage = Veuillez répondre à une demande en attente avec /deny <raison>
+moderator-deny-reason-missing = Veuillez indiquer une raison
+moderator-review-not-pending = Cette demande n'est plus en attente
+moderator-request-missing = Il n'y a aucune demande d'adhésion en attente de {$user_id}
+moderator-user-blocked = {$user_id} est bloqué, utilisez d'abord /unblock
+moderator-user-not-blocked = {$user_id} n'est pas bloqué
+moderator-report-usage = Utilisation : /report <user id> [raison]
+moderator-banlist-added = {$user_id} a été ajouté à la liste de bannissement
+moderator-banlist-updated = L'entrée de {$user_id} dans la liste de bannissement a été mise à jour
+moderator-banlist-removed = {$user_id} a été retiré de la liste de bannissement
+moderator-banlist-missing = {$user_id} n'est pas sur la liste de bannissement
+moderator-banlist-failed = La liste de bannissement n'a pas pu être enregistrée et n'a donc pas été modifiée : {$error}
+moderator-import-usage = Veuillez répondre à un fichier de liste de bannissement avec /importbanlist
+moderator-import-done = {$count} nouvelles entrées importées dans la liste de bannissement
+moderator-import-invalid = Le fichier n'est pas une liste de bannissement valide : {$error}
+moderator-reloaded = La configuration a été rechargée
+moderator-reloaded-restart = La configuration a été rechargée. Les modifications de {$settings} ne prendront effet qu'après un redémarrage.
+moderator-reload-failed = Impossible de recharger la configuration : {$error}
@@ -0,0 +1,892 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use rand::distr::Alphanumeric;
+use sqlx::Row;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use teloxide::types::{ChatId, MessageEntity, MessageId, UserId};
+
+use crate::review::ReviewAction;
+
+pub struct AuditLog {
+    pool: SqlitePool,
+}
+
+const REQUEST_ID_LENGTH: usize = 8;
+
+/// Generates the short ID a join request is referred to by, including in callback data.
+pub fn new_request_id() -> String {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(REQUEST_ID_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RequestStatus {
+    Pending,
+    Decided,
+    Expired,
+}
+
+impl RequestStatus {
+    fn code(self) -> i64 {
+        match self {
+            Self::Pending => 0,
+            Self::Decided => 1,
+            Self::Expired => 2,
+        }
+    }
+
+    fn from_code(code: i64) -> Self {
+        match code {
+            0 => Self::Pending,
+            2 => Self::Expired,
+            _ => Self::Decided,
+        }
+    }
+}
+
+/// A join request along with everything needed to act on it later.
+#[derive(Debug)]
+pub struct RequestRecord {
+    pub request_id: String,
+    /// The primary chat of the group the request was made for.
+    pub chat_id: ChatId,
+    pub user_id: UserId,
+    pub display_name: String,
+    pub username: Option<String>,
+    pub reason: String,
+    pub locale: String,
+    /// Whether the applicant came from a join request, which is only open until it is decided.
+    pub join_request: bool,
+    pub status: RequestStatus,
+    /// The review or summary message in the moderator chat, once posted.
+    pub message_id: Option<MessageId>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// What became of a request, either through a moderator or on its own.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Review(ReviewAction),
+    Expired,
+    Unknown,
+}
+
+impl Outcome {
+    fn code(self) -> i64 {
+        match self {
+            Self::Review(action) => u8::from(action) as i64,
+            Self::Expired => -2,
+            Self::Unknown => -1,
+        }
+    }
+
+    fn from_code(code: i64) -> Self {
+        if code == -2 {
+            return Self::Expired;
+        }
+
+        u8::try_from(code)
+            .ok()
+            .and_then(|action| ReviewAction::try_from(action).ok())
+            .map_or(Self::Unknown, Self::Review)
+    }
+}
+
+#[derive(Debug)]
+pub struct DecisionRecord {
+    pub request_id: String,
+    pub user_id: UserId,
+    pub outcome: Outcome,
+    pub reviewer_id: UserId,
+    pub reviewer: String,
+    pub send_result: String,
+    pub decided_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub requests: i64,
+    pub decisions: Vec<(Outcome, i64)>,
+}
+
+#[derive(Debug)]
+pub struct InviteLinkRecord {
+    pub invite_link: String,
+    pub chat_id: ChatId,
+    pub request_id: String,
+    pub user_id: UserId,
+    pub expires_at: DateTime<Utc>,
+    pub joined_at: Option<DateTime<Utc>>,
+}
+
+pub enum UnusedInviteLinks {
+    User(ChatId, UserId),
+    ExpiredBy(DateTime<Utc>),
+}
+
+/// The review message as last posted, which allows editing it outside of a callback.
+#[derive(Debug)]
+pub struct ReviewMessage {
+    pub request_id: String,
+    pub text: String,
+    pub entities: Vec<MessageEntity>,
+}
+
+#[derive(Debug)]
+pub struct HistoryEntry {
+    pub request: RequestRecord,
+    pub decisions: Vec<DecisionRecord>,
+}
+
+impl AuditLog {
+    pub async fn open(storage_path: Option<&Path>) -> Result<Self, sqlx::Error> {
+        let options = match storage_path {
+            Some(storage_path) => SqliteConnectOptions::new()
+                .filename(storage_path.join("audit_log.sqlite"))
+                .create_if_missing(true),
+            None => SqliteConnectOptions::from_str("sqlite::memory:")?,
+        };
+
+        // An in-memory database only lives as long as its connection, so there must only be one
+        // which is never closed.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS requests (
+                request_id TEXT PRIMARY KEY NOT NULL,
+                chat_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                display_name TEXT NOT NULL,
+                username TEXT,
+                reason TEXT NOT NULL,
+                locale TEXT NOT NULL,
+                join_request INTEGER NOT NULL,
+                status INTEGER NOT NULL,
+                message_id INTEGER,
+                submitted_at INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS decisions (
+                request_id TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                action INTEGER NOT NULL,
+                reviewer_id INTEGER NOT NULL,
+                reviewer TEXT NOT NULL,
+                send_result TEXT NOT NULL,
+                decided_at INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS invite_links (
+                invite_link TEXT PRIMARY KEY NOT NULL,
+                chat_id INTEGER NOT NULL,
+                request_id TEXT NOT NULL,
+                user_id INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                joined_at INTEGER,
+                revoked_at INTEGER
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS review_messages (
+                request_id TEXT PRIMARY KEY NOT NULL,
+                text TEXT NOT NULL,
+                entities TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        Ok(Self { pool })
+    }
+
+    pub async fn record_request(&self, record: &RequestRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO requests
+                (request_id, chat_id, user_id, display_name, username, reason, locale, join_request,
+                status, message_id, submitted_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&record.request_id)
+        .bind(record.chat_id.0)
+        .bind(record.user_id.0 as i64)
+        .bind(&record.display_name)
+        .bind(&record.username)
+        .bind(&record.reason)
+        .bind(&record.locale)
+        .bind(record.join_request)
+        .bind(record.status.code())
+        .bind(record.message_id.map(|message_id| message_id.0))
+        .bind(record.submitted_at.timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Removes a request whose review could not be posted, so that it is not taken for a pending
+    /// one.
+    pub async fn discard_request(&self, request_id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM requests WHERE request_id = ?")
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_request_message(
+        &self,
+        request_id: &str,
+        message_id: MessageId,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE requests SET message_id = ? WHERE request_id = ?")
+            .bind(message_id.0)
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Records a decision and marks its request as no longer pending.
+    pub async fn record_decision(&self, record: &DecisionRecord) -> Result<(), sqlx::Error> {
+        let status = match record.outcome {
+            Outcome::Expired => RequestStatus::Expired,
+            Outcome::Review(ReviewAction::Undo) => RequestStatus::Pending,
+            _ => RequestStatus::Decided,
+        };
+
+        sqlx::query("UPDATE requests SET status = ? WHERE request_id = ?")
+            .bind(status.code())
+            .bind(&record.request_id)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO decisions
+                (request_id, user_id, action, reviewer_id, reviewer, send_result, decided_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&record.request_id)
+        .bind(record.user_id.0 as i64)
+        .bind(record.outcome.code())
+        .bind(record.reviewer_id.0 as i64)
+        .bind(&record.reviewer)
+        .bind(&record.send_result)
+        .bind(record.decided_at.timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn record_review_message(&self, message: &ReviewMessage) -> Result<(), sqlx::Error> {
+        let entities = serde_json::to_string(&message.entities)
+            .map_err(|err| sqlx::Error::Encode(err.into()))?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO review_messages (request_id, text, entities)
+            VALUES (?, ?, ?)",
+        )
+        .bind(&message.request_id)
+        .bind(&message.text)
+        .bind(entities)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replaces the text of a recorded review message after it was edited.
+    pub async fn update_review_message_text(
+        &self,
+        request_id: &str,
+        text: &str,
+        entities: &[MessageEntity],
+    ) -> Result<(), sqlx::Error> {
+        let entities =
+            serde_json::to_string(entities).map_err(|err| sqlx::Error::Encode(err.into()))?;
+
+        sqlx::query("UPDATE review_messages SET text = ?, entities = ? WHERE request_id = ?")
+            .bind(text)
+            .bind(entities)
+            .bind(request_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn review_message(
+        &self,
+        request_id: &str,
+    ) -> Result<Option<ReviewMessage>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM review_messages WHERE request_id = ?")
+            .bind(request_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let entities = serde_json::from_str(row.get("entities"))
+            .map_err(|err| sqlx::Error::Decode(err.into()))?;
+
+        Ok(Some(ReviewMessage {
+            request_id: row.get("request_id"),
+            text: row.get("text"),
+            entities,
+        }))
+    }
+
+    pub async fn record_invite_link(&self, record: &InviteLinkRecord) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO invite_links
+                (invite_link, chat_id, request_id, user_id, expires_at, joined_at)
+            VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&record.invite_link)
+        .bind(record.chat_id.0)
+        .bind(&record.request_id)
+        .bind(record.user_id.0 as i64)
+        .bind(record.expires_at.timestamp())
+        .bind(record.joined_at.map(|joined_at| joined_at.timestamp()))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn invite_link(
+        &self,
+        invite_link: &str,
+    ) -> Result<Option<InviteLinkRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM invite_links WHERE invite_link = ?")
+            .bind(invite_link)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(invite_link_from_row))
+    }
+
+    pub async fn mark_invite_link_joined(
+        &self,
+        invite_link: &str,
+        joined_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE invite_links SET joined_at = ? WHERE invite_link = ? AND joined_at IS NULL",
+        )
+        .bind(joined_at.timestamp())
+        .bind(invite_link)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_invite_link_revoked(
+        &self,
+        invite_link: &str,
+        revoked_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE invite_links SET revoked_at = ? WHERE invite_link = ?")
+            .bind(revoked_at.timestamp())
+            .bind(invite_link)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the links which were neither used nor revoked, either of a single user in a chat or
+    /// of everyone whose link expired by the given time.
+    pub async fn unused_invite_links(
+        &self,
+        filter: UnusedInviteLinks,
+    ) -> Result<Vec<InviteLinkRecord>, sqlx::Error> {
+        let query = "SELECT * FROM invite_links WHERE joined_at IS NULL AND revoked_at IS NULL";
+
+        let rows = match filter {
+            UnusedInviteLinks::User(chat_id, user_id) => {
+                sqlx::query(&format!(
+                    "{query} AND chat_id = ? AND user_id = ? ORDER BY expires_at"
+                ))
+                .bind(chat_id.0)
+                .bind(user_id.0 as i64)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            UnusedInviteLinks::ExpiredBy(time) => {
+                sqlx::query(&format!("{query} AND expires_at <= ? ORDER BY expires_at"))
+                    .bind(time.timestamp())
+                    .fetch_all(&self.pool)
+                    .await?
+            }
+        };
+
+        Ok(rows.iter().map(invite_link_from_row).collect())
+    }
+
+    pub async fn find_user_ids(&self, username: &str) -> Result<Vec<UserId>, sqlx::Error> {
+        let rows =
+            sqlx::query("SELECT DISTINCT user_id FROM requests WHERE username = ? COLLATE NOCASE")
+                .bind(username)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| UserId(row.get::<i64, _>("user_id") as u64))
+            .collect())
+    }
+
+    /// Returns the most recent requests of a user along with their decisions, newest first.
+    pub async fn history(
+        &self,
+        user_id: UserId,
+        limit: u32,
+    ) -> Result<Vec<HistoryEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM requests WHERE user_id = ? ORDER BY submitted_at DESC LIMIT ?",
+        )
+        .bind(user_id.0 as i64)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut entries = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let request = request_from_row(&row);
+            let decisions = self.decisions(&request.request_id).await?;
+            entries.push(HistoryEntry { request, decisions });
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the decisions on a request, oldest first.
+    pub async fn decisions(&self, request_id: &str) -> Result<Vec<DecisionRecord>, sqlx::Error> {
+        let rows = sqlx::query(
+            "SELECT * FROM decisions WHERE request_id = ? ORDER BY decided_at ASC, rowid ASC",
+        )
+        .bind(request_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| DecisionRecord {
+                request_id: row.get("request_id"),
+                user_id: UserId(row.get::<i64, _>("user_id") as u64),
+                outcome: Outcome::from_code(row.get("action")),
+                reviewer_id: UserId(row.get::<i64, _>("reviewer_id") as u64),
+                reviewer: row.get("reviewer"),
+                send_result: row.get("send_result"),
+                decided_at: timestamp(row, "decided_at"),
+            })
+            .collect())
+    }
+
+    pub async fn request(&self, request_id: &str) -> Result<Option<RequestRecord>, sqlx::Error> {
+        let row = sqlx::query("SELECT * FROM requests WHERE request_id = ?")
+            .bind(request_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.as_ref().map(request_from_row))
+    }
+
+    pub async fn latest_request_id(
+        &self,
+        chat_id: ChatId,
+        user_id: UserId,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT request_id FROM requests WHERE chat_id = ? AND user_id = ?
+            ORDER BY submitted_at DESC LIMIT 1",
+        )
+        .bind(chat_id.0)
+        .bind(user_id.0 as i64)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| row.get("request_id")))
+    }
+
+    /// Returns all requests without any decision, oldest first. This includes requests which
+    /// were cancelled by the applicant, so callers have to check whether they are still pending.
+    pub async fn undecided_requests(&self) -> Result<Vec<RequestRecord>, sqlx::Error> {
+        let rows = sqlx::query("SELECT * FROM requests WHERE status = ? ORDER BY submitted_at ASC")
+            .bind(RequestStatus::Pending.code())
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(request_from_row).collect())
+    }
+
+    pub async fn stats(&self) -> Result<Stats, sqlx::Error> {
+        let requests = sqlx::query("SELECT COUNT(*) AS count FROM requests")
+            .fetch_one(&self.pool)
+            .await?
+            .get("count");
+
+        let decisions = sqlx::query(
+            "SELECT action, COUNT(*) AS count FROM decisions GROUP BY action ORDER BY action",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| (Outcome::from_code(row.get("action")), row.get("count")))
+        .collect();
+
+        Ok(Stats {
+            requests,
+            decisions,
+        })
+    }
+}
+
+fn request_from_row(row: &SqliteRow) -> RequestRecord {
+    RequestRecord {
+        request_id: row.get("request_id"),
+        chat_id: ChatId(row.get("chat_id")),
+        user_id: UserId(row.get::<i64, _>("user_id") as u64),
+        display_name: row.get("display_name"),
+        username: row.get("username"),
+        reason: row.get("reason"),
+        locale: row.get("locale"),
+        join_request: row.get("join_request"),
+        status: RequestStatus::from_code(row.get("status")),
+        message_id: row.get::<Option<i32>, _>("message_id").map(MessageId),
+        submitted_at: timestamp(row, "submitted_at"),
+    }
+}
+
+fn invite_link_from_row(row: &SqliteRow) -> InviteLinkRecord {
+    InviteLinkRecord {
+        invite_link: row.get("invite_link"),
+        chat_id: ChatId(row.get("chat_id")),
+        request_id: row.get("request_id"),
+        user_id: UserId(row.get::<i64, _>("user_id") as u64),
+        expires_at: timestamp(row, "expires_at"),
+        joined_at: row
+            .get::<Option<i64>, _>("joined_at")
+            .and_then(|joined_at| DateTime::from_timestamp(joined_at, 0)),
+    }
+}
+
+fn timestamp(row: &SqliteRow, column: &str) -> DateTime<Utc> {
+    DateTime::from_timestamp(row.get(column), 0).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invite_link(invite_link: &str, user_id: u64, expires_at: i64) -> InviteLinkRecord {
+        InviteLinkRecord {
+            invite_link: invite_link.to_string(),
+            chat_id: ChatId(-100),
+            request_id: "1".to_string(),
+            user_id: UserId(user_id),
+            expires_at: DateTime::from_timestamp(expires_at, 0).unwrap(),
+            joined_at: None,
+        }
+    }
+
+    fn request(request_id: &str, user_id: u64, submitted_at: i64) -> RequestRecord {
+        RequestRecord {
+            request_id: request_id.to_string(),
+            chat_id: ChatId(-100),
+            user_id: UserId(user_id),
+            display_name: "Jane Doe (@jane)".to_string(),
+            username: Some("jane".to_string()),
+            reason: "I like this chat".to_string(),
+            locale: "en".to_string(),
+            join_request: false,
+            status: RequestStatus::Pending,
+            message_id: None,
+            submitted_at: DateTime::from_timestamp(submitted_at, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn returns_history_newest_first() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+        audit_log
+            .record_request(&request("1", 42, 100))
+            .await
+            .unwrap();
+        audit_log
+            .record_request(&request("2", 42, 200))
+            .await
+            .unwrap();
+        audit_log
+            .record_request(&request("3", 7, 300))
+            .await
+            .unwrap();
+        audit_log
+            .record_decision(&DecisionRecord {
+                request_id: "1".to_string(),
+                user_id: UserId(42),
+                outcome: Outcome::Review(ReviewAction::Deny),
+                reviewer_id: UserId(1),
+                reviewer: "Moderator".to_string(),
+                send_result: "sent".to_string(),
+                decided_at: DateTime::from_timestamp(150, 0).unwrap(),
+            })
+            .await
+            .unwrap();
+
+        let history = audit_log.history(UserId(42), 10).await.unwrap();
+
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].request.request_id, "2");
+        assert!(history[0].decisions.is_empty());
+        assert_eq!(history[1].request.request_id, "1");
+        assert_eq!(history[1].decisions.len(), 1);
+        assert_eq!(
+            history[1].decisions[0].outcome,
+            Outcome::Review(ReviewAction::Deny)
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_undecided_requests_and_stats() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+        audit_log
+            .record_request(&request("1", 42, 100))
+            .await
+            .unwrap();
+        audit_log
+            .record_request(&request("2", 7, 200))
+            .await
+            .unwrap();
+        audit_log
+            .record_decision(&DecisionRecord {
+                request_id: "1".to_string(),
+                user_id: UserId(42),
+                outcome: Outcome::Review(ReviewAction::Approve),
+                reviewer_id: UserId(1),
+                reviewer: "Moderator".to_string(),
+                send_result: "sent".to_string(),
+                decided_at: DateTime::from_timestamp(150, 0).unwrap(),
+            })
+            .await
+            .unwrap();
+
+        let undecided = audit_log.undecided_requests().await.unwrap();
+        assert_eq!(undecided.len(), 1);
+        assert_eq!(undecided[0].request_id, "2");
+
+        audit_log
+            .set_request_message("2", MessageId(17))
+            .await
+            .unwrap();
+        let request = audit_log.request("2").await.unwrap().unwrap();
+        assert_eq!(request.message_id, Some(MessageId(17)));
+        assert_eq!(
+            audit_log.request("1").await.unwrap().unwrap().status,
+            RequestStatus::Decided
+        );
+
+        audit_log
+            .record_decision(&DecisionRecord {
+                request_id: "1".to_string(),
+                user_id: UserId(42),
+                outcome: Outcome::Review(ReviewAction::Undo),
+                reviewer_id: UserId(1),
+                reviewer: "Moderator".to_string(),
+                send_result: "sent".to_string(),
+                decided_at: DateTime::from_timestamp(160, 0).unwrap(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            audit_log.request("1").await.unwrap().unwrap().status,
+            RequestStatus::Pending
+        );
+        assert_eq!(audit_log.decisions("1").await.unwrap().len(), 2);
+
+        let stats = audit_log.stats().await.unwrap();
+        assert_eq!(stats.requests, 2);
+        assert_eq!(
+            stats.decisions,
+            vec![
+                (Outcome::Review(ReviewAction::Approve), 1),
+                (Outcome::Review(ReviewAction::Undo), 1)
+            ]
+        );
+
+        assert_eq!(
+            audit_log
+                .latest_request_id(ChatId(-100), UserId(42))
+                .await
+                .unwrap(),
+            Some("1".to_string())
+        );
+        assert_eq!(
+            audit_log
+                .latest_request_id(ChatId(-200), UserId(42))
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            audit_log.request("2").await.unwrap().unwrap().user_id,
+            UserId(7)
+        );
+    }
+
+    #[tokio::test]
+    async fn forgets_discarded_requests() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+
+        for record in [request("1", 42, 100), request("2", 42, 200)] {
+            audit_log.record_request(&record).await.unwrap();
+        }
+
+        audit_log.discard_request("2").await.unwrap();
+
+        assert_eq!(
+            audit_log
+                .latest_request_id(ChatId(-100), UserId(42))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("1")
+        );
+        assert_eq!(audit_log.undecided_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn finds_user_ids_by_username() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+        audit_log
+            .record_request(&request("1", 42, 100))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            audit_log.find_user_ids("JANE").await.unwrap(),
+            vec![UserId(42)]
+        );
+        assert!(audit_log.find_user_ids("john").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn round_trips_review_messages_and_outcomes() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+        audit_log
+            .record_review_message(&ReviewMessage {
+                request_id: "1".to_string(),
+                text: "Jane would like to join".to_string(),
+                entities: vec![MessageEntity::bold(0, 4)],
+            })
+            .await
+            .unwrap();
+
+        let message = audit_log.review_message("1").await.unwrap().unwrap();
+        assert_eq!(message.text, "Jane would like to join");
+        assert_eq!(message.entities, vec![MessageEntity::bold(0, 4)]);
+        assert!(audit_log.review_message("2").await.unwrap().is_none());
+
+        for outcome in [
+            Outcome::Review(ReviewAction::Block),
+            Outcome::Expired,
+            Outcome::Unknown,
+        ] {
+            assert_eq!(Outcome::from_code(outcome.code()), outcome);
+        }
+    }
+
+    #[tokio::test]
+    async fn lists_unused_invite_links() {
+        let audit_log = AuditLog::open(None).await.unwrap();
+
+        let other_chat = InviteLinkRecord {
+            chat_id: ChatId(-200),
+            ..invite_link("https://t.me/+e", 42, 300)
+        };
+
+        for record in [
+            invite_link("https://t.me/+a", 42, 100),
+            invite_link("https://t.me/+b", 42, 300),
+            invite_link("https://t.me/+c", 7, 100),
+            invite_link("https://t.me/+d", 7, 100),
+            other_chat,
+        ] {
+            audit_log.record_invite_link(&record).await.unwrap();
+        }
+
+        let at = DateTime::from_timestamp(200, 0).unwrap();
+        audit_log
+            .mark_invite_link_joined("https://t.me/+c", at)
+            .await
+            .unwrap();
+        audit_log
+            .mark_invite_link_revoked("https://t.me/+d", at)
+            .await
+            .unwrap();
+
+        let links = |links: Vec<InviteLinkRecord>| {
+            links
+                .into_iter()
+                .map(|link| link.invite_link)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            links(
+                audit_log
+                    .unused_invite_links(UnusedInviteLinks::ExpiredBy(at))
+                    .await
+                    .unwrap()
+            ),
+            vec!["https://t.me/+a"]
+        );
+        assert_eq!(
+            links(
+                audit_log
+                    .unused_invite_links(UnusedInviteLinks::User(ChatId(-100), UserId(42)))
+                    .await
+                    .unwrap()
+            ),
+            vec!["https://t.me/+a", "https://t.me/+b"]
+        );
+        assert_eq!(
+            audit_log
+                .invite_link("https://t.me/+c")
+                .await
+                .unwrap()
+                .unwrap()
+                .joined_at,
+            Some(at)
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Entry;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use teloxide::types::UserId;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use crate::storage;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BanlistEntry {
+    pub user_id: UserId,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub added_by: Option<String>,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Error)]
+pub enum ImportError {
+    #[error("{0}")]
+    Invalid(#[from] serde_json::Error),
+    #[error("{0}")]
+    Persist(#[from] io::Error),
+}
+
+/// Users reported as scammers by our own moderators, which can be shared with other communities.
+#[derive(Debug)]
+pub struct Banlist {
+    entries: Mutex<BTreeMap<UserId, BanlistEntry>>,
+    file_path: Option<PathBuf>,
+}
+
+impl Banlist {
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            file_path: None,
+        }
+    }
+
+    pub fn load(storage_path: &Path) -> io::Result<Self> {
+        let file_path = storage_path.join("banlist.json");
+
+        let entries = match fs::read(&file_path) {
+            Ok(bytes) => parse(&bytes).map_err(io::Error::other)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self {
+            entries: Mutex::new(entries),
+            file_path: Some(file_path),
+        })
+    }
+
+    pub async fn contains(&self, user_id: UserId) -> bool {
+        self.entries.lock().await.contains_key(&user_id)
+    }
+
+    /// Adds an entry and returns whether the user was not listed before.
+    pub async fn add(&self, entry: BanlistEntry) -> io::Result<bool> {
+        let mut entries = self.entries.lock().await;
+        let mut updated = entries.clone();
+        let added = updated.insert(entry.user_id, entry).is_none();
+
+        self.persist(&updated).await?;
+        *entries = updated;
+        Ok(added)
+    }
+
+    /// Removes a user and returns whether they were listed.
+    pub async fn remove(&self, user_id: UserId) -> io::Result<bool> {
+        let mut entries = self.entries.lock().await;
+        let mut updated = entries.clone();
+
+        if updated.remove(&user_id).is_none() {
+            return Ok(false);
+        }
+
+        self.persist(&updated).await?;
+        *entries = updated;
+        Ok(true)
+    }
+
+    pub async fn export(&self) -> serde_json::Result<Vec<u8>> {
+        let entries = self.entries.lock().await;
+        serde_json::to_vec_pretty(&entries.values().collect::<Vec<_>>())
+    }
+
+    /// Merges an exported banlist into this one, keeping existing entries, and returns the number
+    /// of newly listed users.
+    pub async fn import(&self, bytes: &[u8]) -> Result<usize, ImportError> {
+        let imported = parse(bytes)?;
+        let mut entries = self.entries.lock().await;
+        let mut updated = entries.clone();
+        let mut added = 0;
+
+        for (user_id, entry) in imported {
+            if let Entry::Vacant(vacant) = updated.entry(user_id) {
+                vacant.insert(entry);
+                added += 1;
+            }
+        }
+
+        if added > 0 {
+            self.persist(&updated).await?;
+            *entries = updated;
+        }
+
+        Ok(added)
+    }
+
+    /// Writes the entries to disk, which callers only apply in memory once they are saved.
+    async fn persist(&self, entries: &BTreeMap<UserId, BanlistEntry>) -> io::Result<()> {
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+
+        let bytes =
+            serde_json::to_vec(&entries.values().collect::<Vec<_>>()).map_err(io::Error::other)?;
+        storage::write_atomically(file_path, &bytes).await
+    }
+}
+
+fn parse(bytes: &[u8]) -> serde_json::Result<BTreeMap<UserId, BanlistEntry>> {
+    let entries: Vec<BanlistEntry> = serde_json::from_slice(bytes)?;
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| (entry.user_id, entry))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: u64, reason: &str) -> BanlistEntry {
+        BanlistEntry {
+            user_id: UserId(user_id),
+            reason: Some(reason.to_string()),
+            added_by: Some("Moderator".to_string()),
+            added_at: DateTime::from_timestamp(100, 0).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn imports_without_overwriting_entries() {
+        let source = Banlist::in_memory();
+        source.add(entry(1, "crypto scam")).await.unwrap();
+        source.add(entry(2, "phishing")).await.unwrap();
+
+        let target = Banlist::in_memory();
+        assert!(target.add(entry(1, "spam")).await.unwrap());
+        assert!(!target.add(entry(1, "spam")).await.unwrap());
+
+        let added = target
+            .import(&source.export().await.unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(added, 1);
+        assert!(target.contains(UserId(2)).await);
+        assert_eq!(
+            target.entries.lock().await[&UserId(1)].reason.as_deref(),
+            Some("spam")
+        );
+    }
+
+    #[tokio::test]
+    async fn persists_entries() {
+        let storage_path = tempfile::tempdir().unwrap();
+
+        let banlist = Banlist::load(storage_path.path()).unwrap();
+        banlist.add(entry(1, "crypto scam")).await.unwrap();
+        banlist.add(entry(2, "phishing")).await.unwrap();
+        assert!(banlist.remove(UserId(2)).await.unwrap());
+        assert!(!banlist.remove(UserId(2)).await.unwrap());
+
+        let banlist = Banlist::load(storage_path.path()).unwrap();
+        assert!(banlist.contains(UserId(1)).await);
+        assert!(!banlist.contains(UserId(2)).await);
+    }
+
+    #[tokio::test]
+    async fn keeps_entries_which_could_not_be_saved() {
+        let storage_path = tempfile::tempdir().unwrap();
+        let banlist = Banlist::load(&storage_path.path().join("missing")).unwrap();
+
+        assert!(banlist.add(entry(1, "crypto scam")).await.is_err());
+        assert!(!banlist.contains(UserId(1)).await);
+    }
+}
@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use envconfig::Envconfig;
+use i18n_embed::fluent::{FluentLanguageLoader, fluent_language_loader};
+use log::{error, info, warn};
+use thiserror::Error;
+use toml::Value;
+
+use crate::groups::is_valid_group_name;
+use crate::translations::{self, Translations, TranslationsError};
+use crate::{Config, INVITE_LINK_MAX_MEMBER_LIMIT, LANGUAGE_LOADER, is_valid_secret_token};
+
+/// Every setting which can be given in the config file, named like its environment variable.
+const SETTINGS: &[&str] = &[
+    "PRIMARY_CHAT_ID",
+    "MODERATOR_CHAT_ID",
+    "CHANNEL_ID",
+    "STORAGE_PATH",
+    "MAX_PINNED_CHANNEL_POSTS",
+    "JOIN_QUESTIONS",
+    "RULES_MESSAGE",
+    "RULES_FILE",
+    "DENY_REASONS",
+    "APPROVAL_QUORUM",
+    "DENIAL_QUORUM",
+    "SCAMMER_LIST_SOURCES",
+    "BANLIST_ACTION",
+    "INVITE_LINK_LIFETIME_HOURS",
+    "INVITE_LINK_MEMBER_LIMIT",
+    "REQUEST_EXPIRY_HOURS",
+    "REQUEST_REMINDER_HOURS",
+    "UNDO_WINDOW_SECONDS",
+    "WEBHOOK_URL",
+    "WEBHOOK_LISTEN_ADDRESS",
+    "WEBHOOK_SECRET_TOKEN",
+    "METRICS_LISTEN_ADDRESS",
+    "GROUPS",
+    "MODERATOR_LOCALE",
+];
+
+/// Settings whose environment variables hold JSON rather than a comma-separated list.
+const JSON_SETTINGS: &[&str] = &["SCAMMER_LIST_SOURCES", "GROUPS"];
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Failed to read {0}: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("Failed to parse {0}: {1}")]
+    Parse(PathBuf, toml::de::Error),
+    #[error("Unknown setting {0} in the config file")]
+    UnknownSetting(String),
+    #[error("Unsupported value for {0} in the config file")]
+    UnsupportedValue(String),
+    #[error("{0}")]
+    Env(#[from] envconfig::Error),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("Failed to load translations: {0}")]
+    Translations(#[from] TranslationsError),
+}
+
+/// Loads the configuration from the file named by `CONFIG_FILE`, if any, with environment
+/// variables taking precedence.
+pub fn load() -> Result<Config, ConfigError> {
+    let config = read()?;
+    apply_translations(&config)?;
+    Ok(config)
+}
+
+fn read() -> Result<Config, ConfigError> {
+    let mut vars = match env::var_os("CONFIG_FILE") {
+        Some(path) => read_file(Path::new(&path))?,
+        None => HashMap::new(),
+    };
+
+    vars.extend(
+        env::vars_os().filter_map(|(name, value)| {
+            Some((name.into_string().ok()?, value.into_string().ok()?))
+        }),
+    );
+
+    Ok(Config::init_from_hashmap(&vars)?)
+}
+
+/// Validates the configuration against the translation overrides in its storage path, which
+/// replace those in use only once it passed.
+fn apply_translations(config: &Config) -> Result<(), ConfigError> {
+    let translations = Translations::read(&LANGUAGE_LOADER, config.storage_path.as_deref())?;
+
+    // Overrides may add messages which the settings refer to, e.g. further join questions.
+    let loader: FluentLanguageLoader = fluent_language_loader!();
+    translations.apply(&loader)?;
+    validate(config, &loader)?;
+
+    translations.apply(&LANGUAGE_LOADER)?;
+    translations::log_reports(translations.reports());
+    Ok(())
+}
+
+fn read_file(path: &Path) -> Result<HashMap<String, String>, ConfigError> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| ConfigError::Read(path.to_path_buf(), err))?;
+    let table =
+        toml::from_str(&contents).map_err(|err| ConfigError::Parse(path.to_path_buf(), err))?;
+
+    let mut vars = HashMap::new();
+    flatten("", table, &mut vars)?;
+    Ok(vars)
+}
+
+/// Maps the settings of a file to their environment variable names, so that `[webhook]` with
+/// `url` becomes `WEBHOOK_URL`.
+fn flatten(
+    prefix: &str,
+    table: toml::value::Table,
+    vars: &mut HashMap<String, String>,
+) -> Result<(), ConfigError> {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+
+        if let Value::Table(table) = value {
+            flatten(&path, table, vars)?;
+            continue;
+        }
+
+        let name = path.replace('.', "_").to_uppercase();
+
+        if !SETTINGS.contains(&name.as_str()) {
+            return Err(ConfigError::UnknownSetting(path));
+        }
+
+        let value = match value {
+            Value::String(value) => value,
+            Value::Integer(value) => value.to_string(),
+            Value::Boolean(value) => value.to_string(),
+            Value::Array(values) if JSON_SETTINGS.contains(&name.as_str()) => {
+                serde_json::to_string(&values).map_err(|_| ConfigError::UnsupportedValue(path))?
+            }
+            Value::Array(values) => values
+                .into_iter()
+                .map(|value| match value {
+                    Value::String(value) => Some(value),
+                    Value::Integer(value) => Some(value.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(|| ConfigError::UnsupportedValue(path.clone()))?
+                .join(","),
+            _ => return Err(ConfigError::UnsupportedValue(path)),
+        };
+
+        vars.insert(name, value);
+    }
+
+    Ok(())
+}
+
+fn validate(config: &Config, loader: &FluentLanguageLoader) -> Result<(), ConfigError> {
+    let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+    if !loader
+        .current_languages()
+        .iter()
+        .any(|language| language.language == config.moderator_locale.language)
+    {
+        return invalid(format!(
+            "MODERATOR_LOCALE references unknown language \"{}\"",
+            config.moderator_locale
+        ));
+    }
+
+    for question in config.join_questions() {
+        if !loader.has(question) {
+            return invalid(format!(
+                "JOIN_QUESTIONS references unknown message \"{question}\""
+            ));
+        }
+    }
+
+    for reason in config.deny_reasons() {
+        if !loader.has(reason) {
+            return invalid(format!(
+                "DENY_REASONS references unknown message \"{reason}\""
+            ));
+        }
+    }
+
+    if config.deny_reasons().len() > u8::MAX as usize {
+        return invalid(format!(
+            "DENY_REASONS must not contain more than {} reasons",
+            u8::MAX
+        ));
+    }
+
+    if config.invite_link_lifetime_hours == 0 {
+        return invalid("INVITE_LINK_LIFETIME_HOURS must be greater than zero".to_string());
+    }
+
+    if !(1..=INVITE_LINK_MAX_MEMBER_LIMIT).contains(&config.invite_link_member_limit) {
+        return invalid(format!(
+            "INVITE_LINK_MEMBER_LIMIT must be between 1 and {INVITE_LINK_MAX_MEMBER_LIMIT}"
+        ));
+    }
+
+    if config.approval_quorum == 0 || config.denial_quorum == 0 {
+        return invalid("APPROVAL_QUORUM and DENIAL_QUORUM must be greater than zero".to_string());
+    }
+
+    if config.request_expiry_hours == Some(0) || config.request_reminder_hours == Some(0) {
+        return invalid(
+            "REQUEST_EXPIRY_HOURS and REQUEST_REMINDER_HOURS must be greater than zero".to_string(),
+        );
+    }
+
+    if let Some(secret_token) = &config.webhook_secret_token {
+        if config.webhook_url.is_none() {
+            return invalid("WEBHOOK_SECRET_TOKEN requires WEBHOOK_URL to be set".to_string());
+        }
+
+        if !is_valid_secret_token(secret_token) {
+            return invalid(
+                "WEBHOOK_SECRET_TOKEN must be 1 to 256 characters long and only contain A-Z, \
+                a-z, 0-9, _ and -"
+                    .to_string(),
+            );
+        }
+    }
+
+    match (&config.rules_message, &config.rules_file) {
+        (Some(_), Some(_)) => {
+            return invalid(
+                "RULES_MESSAGE and RULES_FILE cannot be set at the same time".to_string(),
+            );
+        }
+        (Some(message_id), None) if !loader.has(message_id) => {
+            return invalid(format!(
+                "RULES_MESSAGE references unknown message \"{message_id}\""
+            ));
+        }
+        _ => {}
+    }
+
+    if config.storage_path.is_none() {
+        if config.rules_file.is_some() {
+            return invalid("RULES_FILE requires STORAGE_PATH to be set".to_string());
+        }
+
+        if config.max_pinned_channel_posts.is_some() {
+            return invalid("MAX_PINNED_CHANNEL_POSTS requires STORAGE_PATH to be set".to_string());
+        }
+    }
+
+    validate_groups(config, loader)
+}
+
+fn validate_groups(config: &Config, loader: &FluentLanguageLoader) -> Result<(), ConfigError> {
+    let invalid = |message: String| Err(ConfigError::Invalid(message));
+
+    let mut names = Vec::new();
+    let mut chat_ids = vec![config.primary_chat_id, config.moderator_chat_id];
+    chat_ids.extend(config.channel_id);
+
+    for group in config.groups() {
+        if !is_valid_group_name(&group.name) {
+            return invalid(format!(
+                "GROUPS contains the invalid name \"{}\", which must be 1 to 64 characters long \
+                and only contain A-Z, a-z, 0-9, _ and -",
+                group.name
+            ));
+        }
+
+        if names.contains(&&group.name) {
+            return invalid(format!("GROUPS contains \"{}\" more than once", group.name));
+        }
+
+        names.push(&group.name);
+
+        for chat_id in [group.primary_chat_id, group.moderator_chat_id]
+            .into_iter()
+            .chain(group.channel_id)
+        {
+            if chat_ids.contains(&chat_id) {
+                return invalid(format!(
+                    "GROUPS reuses the chat {chat_id}, while every group needs its own chats"
+                ));
+            }
+
+            chat_ids.push(chat_id);
+        }
+
+        for message_id in group
+            .join_questions
+            .iter()
+            .flatten()
+            .chain(&group.rules_message)
+        {
+            if !loader.has(message_id) {
+                return invalid(format!(
+                    "GROUPS references unknown message \"{message_id}\" for \"{}\"",
+                    group.name
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores the settings which are only read at startup, and returns those which were changed.
+fn keep_structural_settings(config: &mut Config, current: &Config) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+
+    macro_rules! keep {
+        ($($field:ident => $name:literal),* $(,)?) => {
+            $(
+                if config.$field != current.$field {
+                    changed.push($name);
+                    config.$field = current.$field.clone();
+                }
+            )*
+        };
+    }
+
+    keep! {
+        primary_chat_id => "PRIMARY_CHAT_ID",
+        moderator_chat_id => "MODERATOR_CHAT_ID",
+        channel_id => "CHANNEL_ID",
+        storage_path => "STORAGE_PATH",
+        max_pinned_channel_posts => "MAX_PINNED_CHANNEL_POSTS",
+        rules_message => "RULES_MESSAGE",
+        rules_file => "RULES_FILE",
+        // Review buttons refer to deny reasons by their position.
+        deny_reasons => "DENY_REASONS",
+        scammer_list_sources => "SCAMMER_LIST_SOURCES",
+        banlist_action => "BANLIST_ACTION",
+        webhook_url => "WEBHOOK_URL",
+        webhook_listen_address => "WEBHOOK_LISTEN_ADDRESS",
+        webhook_secret_token => "WEBHOOK_SECRET_TOKEN",
+        metrics_listen_address => "METRICS_LISTEN_ADDRESS",
+        groups => "GROUPS",
+    }
+
+    changed
+}
+
+/// Holds the current configuration, which handlers receive as an `Arc<Config>` per update.
+pub struct ConfigHandle(RwLock<Arc<Config>>);
+
+impl ConfigHandle {
+    pub fn new(config: Arc<Config>) -> Self {
+        Self(RwLock::new(config))
+    }
+
+    pub fn current(&self) -> Arc<Config> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// Reloads the configuration and returns the changed settings which require a restart.
+    pub fn reload(&self) -> Result<Vec<&'static str>, ConfigError> {
+        let mut config = read()?;
+        let ignored = keep_structural_settings(&mut config, &self.current());
+        apply_translations(&config)?;
+        *self.0.write().unwrap() = Arc::new(config);
+        Ok(ignored)
+    }
+}
+
+/// Reloads the configuration whenever the process receives `SIGHUP`.
+#[cfg(unix)]
+pub async fn reload_on_hangup(handle: Arc<ConfigHandle>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(err) => {
+            error!("failed to listen for SIGHUP: {err}");
+            return;
+        }
+    };
+
+    while hangup.recv().await.is_some() {
+        match handle.reload() {
+            Ok(ignored) if ignored.is_empty() => info!("configuration reloaded"),
+            Ok(ignored) => warn!(
+                "configuration reloaded, changes to {} require a restart",
+                ignored.join(", ")
+            ),
+            Err(err) => error!("failed to reload configuration: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flattened(contents: &str) -> Result<HashMap<String, String>, ConfigError> {
+        let mut vars = HashMap::new();
+        flatten("", toml::from_str(contents).unwrap(), &mut vars)?;
+        Ok(vars)
+    }
+
+    #[test]
+    fn maps_sections_to_variable_names() {
+        let vars = flattened(
+            r#"
+            primary_chat_id = -100
+            join_questions = ["question-age", "question-source"]
+
+            [webhook]
+            url = "https://example.com/bot"
+
+            [[scammer_list.sources]]
+            name = "Federation"
+            url = "https://example.com/banlist.txt"
+            format = "text"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(vars["PRIMARY_CHAT_ID"], "-100");
+        assert_eq!(vars["JOIN_QUESTIONS"], "question-age,question-source");
+        assert_eq!(vars["WEBHOOK_URL"], "https://example.com/bot");
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&vars["SCAMMER_LIST_SOURCES"]).unwrap(),
+            serde_json::json!([{
+                "name": "Federation",
+                "url": "https://example.com/banlist.txt",
+                "format": "text",
+            }])
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_settings() {
+        assert!(matches!(
+            flattened("[webhook]\nadress = \"0.0.0.0:8080\""),
+            Err(ConfigError::UnknownSetting(path)) if path == "webhook.adress"
+        ));
+    }
+
+    #[test]
+    fn rejects_unsupported_values() {
+        assert!(matches!(
+            flattened("approval_quorum = 1.5"),
+            Err(ConfigError::UnsupportedValue(path)) if path == "approval_quorum"
+        ));
+    }
+
+    #[test]
+    fn rejects_quorums_of_zero() {
+        let config = Config::init_from_hashmap(&HashMap::from([
+            ("PRIMARY_CHAT_ID".to_string(), "-100".to_string()),
+            ("MODERATOR_CHAT_ID".to_string(), "-200".to_string()),
+            ("DENIAL_QUORUM".to_string(), "0".to_string()),
+        ]))
+        .unwrap();
+
+        assert!(matches!(
+            validate(&config, &LANGUAGE_LOADER),
+            Err(ConfigError::Invalid(message)) if message.contains("DENIAL_QUORUM")
+        ));
+    }
+
+    #[test]
+    fn rejects_groups_sharing_chats() {
+        let config = Config::init_from_hashmap(&HashMap::from([
+            ("PRIMARY_CHAT_ID".to_string(), "-100".to_string()),
+            ("MODERATOR_CHAT_ID".to_string(), "-200".to_string()),
+            (
+                "GROUPS".to_string(),
+                r#"[{"name": "other", "primary_chat_id": -101, "moderator_chat_id": -200}]"#
+                    .to_string(),
+            ),
+        ]))
+        .unwrap();
+
+        assert!(matches!(
+            validate_groups(&config, &LANGUAGE_LOADER),
+            Err(ConfigError::Invalid(message)) if message.contains("-200")
+        ));
+    }
+
+    #[test]
+    fn keeps_translations_of_invalid_configurations() {
+        let storage_path = tempfile::tempdir().unwrap();
+        let directory = storage_path.path().join("i18n/en");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(
+            directory.join("telegram_bouncer_bot.ftl"),
+            "question-pets = Do you have pets?",
+        )
+        .unwrap();
+
+        let config = Config::init_from_hashmap(&HashMap::from([
+            ("PRIMARY_CHAT_ID".to_string(), "-100".to_string()),
+            ("MODERATOR_CHAT_ID".to_string(), "-200".to_string()),
+            (
+                "STORAGE_PATH".to_string(),
+                storage_path.path().display().to_string(),
+            ),
+            (
+                "JOIN_QUESTIONS".to_string(),
+                "question-pets,question-missing".to_string(),
+            ),
+        ]))
+        .unwrap();
+
+        assert!(matches!(
+            apply_translations(&config),
+            Err(ConfigError::Invalid(message)) if message.contains("question-missing")
+        ));
+        assert!(!LANGUAGE_LOADER.has("question-pets"));
+    }
+
+    #[test]
+    fn keeps_structural_settings_on_reload() {
+        let vars = |quorum: &str, chat_id: &str| {
+            HashMap::from([
+                ("PRIMARY_CHAT_ID".to_string(), chat_id.to_string()),
+                ("MODERATOR_CHAT_ID".to_string(), "-200".to_string()),
+                ("APPROVAL_QUORUM".to_string(), quorum.to_string()),
+            ])
+        };
+        let current = Config::init_from_hashmap(&vars("1", "-100")).unwrap();
+        let mut config = Config::init_from_hashmap(&vars("2", "-300")).unwrap();
+
+        let changed = keep_structural_settings(&mut config, &current);
+
+        assert_eq!(changed, ["PRIMARY_CHAT_ID"]);
+        assert_eq!(config.primary_chat_id, -100);
+        assert_eq!(config.approval_quorum, 2);
+    }
+}
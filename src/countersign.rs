@@ -1,85 +1,364 @@
-use log::{error, info};
+use log::{error, info, warn};
 use reqwest::StatusCode;
-use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 use teloxide::types::UserId;
+use thiserror::Error;
 use tokio::sync::RwLock;
 use tokio::time::Instant;
 
-#[derive(Debug, Clone)]
+use crate::banlist::Banlist;
+use crate::metrics::METRICS;
+use crate::storage;
+
+const BANLIST_NAME: &str = "Local banlist";
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Source {
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub format: SourceFormat,
+    #[serde(default = "default_refresh_minutes")]
+    pub refresh_minutes: u64,
+    #[serde(default)]
+    pub action: ScammerAction,
+}
+
+fn default_refresh_minutes() -> u64 {
+    15
+}
+
+impl Default for Source {
+    fn default() -> Self {
+        Self {
+            name: "Countersign".to_string(),
+            url: "https://countersign.chat/api/scammer_ids.json".to_string(),
+            format: SourceFormat::Json,
+            refresh_minutes: default_refresh_minutes(),
+            action: ScammerAction::Label,
+        }
+    }
+}
+
+/// What happens to an applicant who is listed by a source, ordered from mildest to strictest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScammerAction {
+    /// Post the review as usual, tagged with the source.
+    #[default]
+    Label,
+    /// Deny the request without posting a review or telling the applicant.
+    Deny,
+    /// Block the applicant from interacting with the bot.
+    Block,
+    /// Ban the applicant from the primary chat and block them.
+    Ban,
+}
+
+impl FromStr for ScammerAction {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "label" => Ok(Self::Label),
+            "deny" => Ok(Self::Deny),
+            "block" => Ok(Self::Block),
+            "ban" => Ok(Self::Ban),
+            _ => Err(format!("unknown action \"{value}\"")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceMatch {
+    pub name: String,
+    pub action: ScammerAction,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceFormat {
+    /// A JSON array of user IDs, given either as strings or as numbers.
+    #[default]
+    Json,
+    /// One user ID per line, ignoring empty lines and lines starting with `#`.
+    Text,
+}
+
+impl SourceFormat {
+    fn parse(self, body: &str) -> Result<HashSet<UserId>, String> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Id {
+            Number(u64),
+            String(String),
+        }
+
+        match self {
+            Self::Json => serde_json::from_str::<Vec<Id>>(body)
+                .map_err(|err| err.to_string())?
+                .into_iter()
+                .map(|id| match id {
+                    Id::Number(id) => Ok(UserId(id)),
+                    Id::String(id) => id.parse().map(UserId).map_err(|err| err.to_string()),
+                })
+                .collect(),
+            Self::Text => body
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(|line| line.parse().map(UserId).map_err(|err| err.to_string()))
+                .collect(),
+        }
+    }
+}
+
+/// The configured scammer list sources, which default to the Countersign list.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sources(pub Vec<Source>);
+
+impl Default for Sources {
+    fn default() -> Self {
+        Self(vec![Source::default()])
+    }
+}
+
+impl FromStr for Sources {
+    type Err = serde_json::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(value).map(Self)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct CachedResponse {
     ids: HashSet<UserId>,
     etag: Option<String>,
-    last_updated: Instant,
+    /// When the list was last fetched by this process, which is unset for lists loaded from
+    /// disk, so that those are refreshed right away.
+    #[serde(skip)]
+    last_updated: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct CachedSource {
+    source: Source,
+    state: RwLock<Option<CachedResponse>>,
+}
+
+impl CachedSource {
+    fn is_stale(&self, state: Option<&CachedResponse>) -> bool {
+        let stale_after = Duration::from_secs(self.source.refresh_minutes * 60);
+
+        state
+            .and_then(|state| state.last_updated)
+            .is_none_or(|last_updated| last_updated.elapsed() >= stale_after)
+    }
+}
+
+#[derive(Debug, Error)]
+enum FetchError {
+    #[error("{0}")]
+    Request(#[from] reqwest::Error),
+    #[error("invalid list: {0}")]
+    Parse(String),
 }
 
-#[derive(Debug, Clone)]
+const REFRESH_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
 pub struct Countersign {
-    state: Arc<RwLock<Option<CachedResponse>>>,
+    sources: Vec<CachedSource>,
+    banlist: Arc<Banlist>,
+    banlist_action: ScammerAction,
     client: reqwest::Client,
+    file_path: Option<PathBuf>,
 }
 
 impl Countersign {
-    pub fn new() -> Self {
+    pub fn new(sources: Vec<Source>, banlist: Arc<Banlist>, banlist_action: ScammerAction) -> Self {
+        Self::with_cache(sources, banlist, banlist_action, HashMap::new(), None)
+    }
+
+    /// Loads the lists cached under the storage path, keyed by their source URL.
+    pub fn load(
+        storage_path: &Path,
+        sources: Vec<Source>,
+        banlist: Arc<Banlist>,
+        banlist_action: ScammerAction,
+    ) -> io::Result<Self> {
+        let file_path = storage_path.join("scammer_lists.json");
+
+        let cache: HashMap<String, CachedResponse> = match fs::read(&file_path) {
+            // The cache is refetched anyway, so a corrupt one only delays matching applicants.
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                warn!("ignoring corrupt scammer list cache: {err}");
+                HashMap::new()
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(Self::with_cache(
+            sources,
+            banlist,
+            banlist_action,
+            cache,
+            Some(file_path),
+        ))
+    }
+
+    fn with_cache(
+        sources: Vec<Source>,
+        banlist: Arc<Banlist>,
+        banlist_action: ScammerAction,
+        mut cache: HashMap<String, CachedResponse>,
+        file_path: Option<PathBuf>,
+    ) -> Self {
         Self {
-            state: Arc::new(RwLock::new(None)),
+            sources: sources
+                .into_iter()
+                .map(|source| CachedSource {
+                    state: RwLock::new(cache.remove(&source.url)),
+                    source,
+                })
+                .collect(),
+            banlist,
+            banlist_action,
             client: reqwest::Client::new(),
+            file_path,
+        }
+    }
+
+    /// Returns all sources which list the user as a scammer, including the local banlist.
+    pub async fn matching_sources(&self, user_id: UserId) -> Vec<SourceMatch> {
+        let mut matches = Vec::new();
+
+        if self.banlist.contains(user_id).await {
+            matches.push(SourceMatch {
+                name: BANLIST_NAME.to_string(),
+                action: self.banlist_action,
+            });
+        }
+
+        for source in &self.sources {
+            let state = source.state.read().await;
+
+            if state
+                .as_ref()
+                .is_some_and(|state| state.ids.contains(&user_id))
+            {
+                matches.push(SourceMatch {
+                    name: source.source.name.clone(),
+                    action: source.source.action,
+                });
+            }
         }
+
+        for source in &matches {
+            METRICS.record_scammer_list_hit(&source.name);
+        }
+
+        matches
     }
 
-    pub async fn is_known_scammer(&self, user_id: UserId) -> bool {
-        let stale_after = Duration::from_secs(15 * 60);
+    /// Refreshes stale lists in the background, starting with those loaded from disk.
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(REFRESH_CHECK_INTERVAL);
 
-        let (cached_result, etag) = {
-            let state = self.state.read().await;
+        loop {
+            interval.tick().await;
 
-            match state.as_ref() {
-                Some(state) => {
-                    let result = state.ids.contains(&user_id);
+            if self.refresh().await {
+                self.persist().await;
+            }
+        }
+    }
 
-                    if state.last_updated.elapsed() < stale_after {
-                        return result;
-                    }
+    /// Refreshes all stale lists and returns whether any of them changed.
+    async fn refresh(&self) -> bool {
+        let mut changed = false;
 
-                    (result, state.etag.clone())
+        for source in &self.sources {
+            let etag = {
+                let state = source.state.read().await;
+
+                if !source.is_stale(state.as_ref()) {
+                    continue;
+                }
+
+                state.as_ref().and_then(|state| state.etag.clone())
+            };
+
+            let name = &source.source.name;
+
+            match Self::fetch(&self.client, &source.source, etag).await {
+                Ok(Some(updated)) => {
+                    info!("{name} list updated");
+                    *source.state.write().await = Some(updated);
+                    changed = true;
+                }
+                Ok(None) => {
+                    info!("{name} list not modified");
+
+                    if let Some(state) = source.state.write().await.as_mut() {
+                        state.last_updated = Some(Instant::now());
+                    }
+                }
+                Err(err) => {
+                    // Retried on the next check, while the previous list stays in use.
+                    error!("failed to fetch {name} list: {err}");
+                    METRICS.record_scammer_list_fetch_failure(name);
                 }
-                None => (false, None),
             }
+        }
+
+        changed
+    }
+
+    async fn persist(&self) {
+        let Some(file_path) = &self.file_path else {
+            return;
         };
 
-        let state = self.state.clone();
+        let mut cache = HashMap::new();
 
-        match Self::fetch(&self.client, etag).await {
-            Ok(Some(updated)) => {
-                info!("countersign list updated");
-                let mut state = state.write().await;
-                let result = updated.ids.contains(&user_id);
-                *state = Some(updated);
-                result
-            }
-            Ok(None) => {
-                info!("countersign list not modified");
-                cached_result
-            }
-            Err(err) => {
-                error!("failed to fetch countersign list: {err}");
-                cached_result
+        for source in &self.sources {
+            if let Some(state) = source.state.read().await.as_ref() {
+                cache.insert(source.source.url.clone(), state.clone());
             }
         }
+
+        let result = match serde_json::to_vec(&cache) {
+            Ok(bytes) => storage::write_atomically(file_path, &bytes).await,
+            Err(err) => Err(io::Error::other(err)),
+        };
+
+        if let Err(err) = result {
+            error!("failed to persist scammer lists: {err}");
+        }
     }
 
     async fn fetch(
         client: &reqwest::Client,
+        source: &Source,
         etag: Option<String>,
-    ) -> Result<Option<CachedResponse>, reqwest::Error> {
-        let mut req = client.get("https://countersign.chat/api/scammer_ids.json");
+    ) -> Result<Option<CachedResponse>, FetchError> {
+        let mut req = client.get(&source.url);
 
         if let Some(tag) = etag {
             req = req.header("If-None-Match", tag);
         }
 
-        let resp = req.send().await?;
+        let resp = req.send().await?.error_for_status()?;
 
         if resp.status() == StatusCode::NOT_MODIFIED {
             return Ok(None);
@@ -91,25 +370,135 @@ impl Countersign {
             .and_then(|v| v.to_str().ok())
             .map(|s| s.to_string());
 
-        let json = resp.json::<Vec<String>>().await?;
+        let body = resp.text().await?;
 
-        let parsed: Result<HashSet<UserId>, _> = json
-            .into_iter()
-            .map(|s| s.parse::<u64>().map(UserId))
-            .collect();
-
-        let ids = match parsed {
-            Ok(ids) => ids,
-            Err(err) => {
-                error!("failed to parse countersign list: {err}");
-                return Ok(None);
-            }
-        };
+        let ids = source.format.parse(&body).map_err(FetchError::Parse)?;
 
         Ok(Some(CachedResponse {
             ids,
             etag,
-            last_updated: Instant::now(),
+            last_updated: Some(Instant::now()),
         }))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::banlist::BanlistEntry;
+    use chrono::Utc;
+
+    #[test]
+    fn parses_sources_with_defaults() {
+        let sources: Sources = r#"[
+            {"name": "Federation", "url": "https://example.com/bans.txt", "format": "text"},
+            {"name": "Mirror", "url": "http://localhost/ids.json", "refresh_minutes": 5, "action": "ban"}
+        ]"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(sources.0[0].format, SourceFormat::Text);
+        assert_eq!(sources.0[0].refresh_minutes, 15);
+        assert_eq!(sources.0[1].format, SourceFormat::Json);
+        assert_eq!(sources.0[1].refresh_minutes, 5);
+        assert_eq!(sources.0[0].action, ScammerAction::Label);
+        assert_eq!(sources.0[1].action, ScammerAction::Ban);
+        assert!(ScammerAction::Ban > ScammerAction::Deny);
+    }
+
+    #[test]
+    fn parses_list_formats() {
+        assert_eq!(
+            SourceFormat::Json.parse(r#"["1", 2]"#).unwrap(),
+            HashSet::from([UserId(1), UserId(2)])
+        );
+        assert_eq!(
+            SourceFormat::Text.parse("# banned\n1\n\n 2 \n").unwrap(),
+            HashSet::from([UserId(1), UserId(2)])
+        );
+        assert!(SourceFormat::Text.parse("abc").is_err());
+    }
+
+    #[test]
+    fn ignores_corrupt_caches() {
+        let storage_path = tempfile::tempdir().unwrap();
+        fs::write(
+            storage_path.path().join("scammer_lists.json"),
+            "{\"https://",
+        )
+        .unwrap();
+
+        let countersign = Countersign::load(
+            storage_path.path(),
+            vec![Source::default()],
+            Arc::new(Banlist::in_memory()),
+            ScammerAction::Label,
+        )
+        .unwrap();
+
+        assert!(countersign.sources[0].state.try_read().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn persists_and_loads_lists() {
+        let storage_path = tempfile::tempdir().unwrap();
+        let source = Source::default();
+
+        let banlist = Arc::new(Banlist::in_memory());
+        let countersign = Countersign::load(
+            storage_path.path(),
+            vec![source.clone()],
+            banlist.clone(),
+            ScammerAction::Label,
+        )
+        .unwrap();
+        *countersign.sources[0].state.write().await = Some(CachedResponse {
+            ids: HashSet::from([UserId(42)]),
+            etag: Some("\"abc\"".to_string()),
+            last_updated: Some(Instant::now()),
+        });
+        countersign.persist().await;
+
+        let countersign = Countersign::load(
+            storage_path.path(),
+            vec![source],
+            banlist.clone(),
+            ScammerAction::Block,
+        )
+        .unwrap();
+        assert_eq!(
+            countersign.matching_sources(UserId(42)).await,
+            vec![SourceMatch {
+                name: "Countersign".to_string(),
+                action: ScammerAction::Label,
+            }]
+        );
+        assert!(countersign.matching_sources(UserId(7)).await.is_empty());
+
+        banlist
+            .add(BanlistEntry {
+                user_id: UserId(42),
+                reason: None,
+                added_by: None,
+                added_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            countersign
+                .matching_sources(UserId(42))
+                .await
+                .into_iter()
+                .map(|source| (source.name, source.action))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Local banlist".to_string(), ScammerAction::Block),
+                ("Countersign".to_string(), ScammerAction::Label),
+            ]
+        );
+
+        let state = countersign.sources[0].state.read().await;
+        assert_eq!(state.as_ref().unwrap().etag.as_deref(), Some("\"abc\""));
+        assert!(countersign.sources[0].is_stale(state.as_ref()));
+    }
+}
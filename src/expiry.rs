@@ -0,0 +1,229 @@
+use std::slice;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{TimeDelta, Utc};
+use i18n_embed::LanguageLoader;
+use i18n_embed::fluent::{FluentLanguageLoader, NegotiationStrategy};
+use i18n_embed::unic_langid::LanguageIdentifier;
+use i18n_embed_fl::fl;
+use log::{error, info, warn};
+use teloxide::prelude::*;
+use teloxide::types::{MessageId, ReplyParameters};
+
+use crate::audit_log::{AuditLog, DecisionRecord, Outcome, RequestRecord, UnusedInviteLinks};
+use crate::config::ConfigHandle;
+use crate::groups::{GroupStates, group_configs};
+use crate::review::RequestLocks;
+use crate::{
+    Config, HandlerResult, JoinStorage, LANGUAGE_LOADER, State, annotate_review, check_send_result,
+    decline_join_request, revoke_invite_links, send_result_note,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically reminds moderators of pending requests, expires those nobody decided on and
+/// revokes invite links which expired unused.
+pub async fn run(
+    bot: Bot,
+    group_states: Arc<GroupStates>,
+    config: Arc<ConfigHandle>,
+    audit_log: Arc<AuditLog>,
+    locks: Arc<RequestLocks>,
+) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        let config = config.current();
+
+        if let Err(err) =
+            revoke_invite_links(&bot, &audit_log, UnusedInviteLinks::ExpiredBy(Utc::now())).await
+        {
+            error!("failed to revoke expired invite links: {err}");
+        }
+
+        if config.request_expiry().is_none() && config.request_reminder().is_none() {
+            continue;
+        }
+
+        let requests = match audit_log.undecided_requests().await {
+            Ok(requests) => requests,
+            Err(err) => {
+                error!("failed to load pending requests: {err}");
+                continue;
+            }
+        };
+
+        let configs = group_configs(&config);
+
+        for request in requests {
+            // Requests of groups which are no longer served are left alone.
+            let Some((config, state)) = configs
+                .iter()
+                .find(|config| ChatId(config.primary_chat_id) == request.chat_id)
+                .zip(group_states.get(request.chat_id))
+            else {
+                continue;
+            };
+
+            let _guard = locks.lock(&request.request_id).await;
+
+            if let Err(err) =
+                check_request(&bot, &state.storage, config, &audit_log, &request).await
+            {
+                error!(
+                    "failed to check pending request {}: {err}",
+                    request.request_id
+                );
+            }
+        }
+    }
+}
+
+async fn check_request(
+    bot: &Bot,
+    storage: &JoinStorage,
+    config: &Config,
+    audit_log: &AuditLog,
+    request: &RequestRecord,
+) -> HandlerResult {
+    let chat_id = ChatId(request.user_id.0 as i64);
+
+    let Some(State::AwaitApproval {
+        message_id,
+        join_request,
+        votes,
+        reminded_at,
+    }) = storage.clone().get_dialogue(chat_id).await?
+    else {
+        return Ok(());
+    };
+
+    if Some(message_id) != request.message_id {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let age = now - request.submitted_at;
+
+    if let Some(expiry) = config.request_expiry()
+        && age >= expiry
+    {
+        return expire(
+            bot,
+            storage,
+            config,
+            audit_log,
+            request,
+            message_id,
+            join_request,
+        )
+        .await;
+    }
+
+    let Some(reminder) = config.request_reminder() else {
+        return Ok(());
+    };
+
+    if now - reminded_at.unwrap_or(request.submitted_at) < reminder {
+        return Ok(());
+    }
+
+    let loader = config.moderator_loader();
+    let text = match config.request_expiry() {
+        Some(expiry) => fl!(
+            loader,
+            "moderator-pending-for-expiring",
+            age = format_hours(&loader, age),
+            remaining = format_hours(&loader, expiry - age)
+        ),
+        None => fl!(
+            loader,
+            "moderator-pending-for",
+            age = format_hours(&loader, age)
+        ),
+    };
+
+    bot.send_message(ChatId(config.moderator_chat_id), text)
+        .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply())
+        .await?;
+
+    storage
+        .clone()
+        .update_dialogue(
+            chat_id,
+            State::AwaitApproval {
+                message_id,
+                join_request,
+                votes,
+                reminded_at: Some(now),
+            },
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn expire(
+    bot: &Bot,
+    storage: &JoinStorage,
+    config: &Config,
+    audit_log: &AuditLog,
+    request: &RequestRecord,
+    message_id: MessageId,
+    join_request: bool,
+) -> HandlerResult {
+    let chat_id = ChatId(request.user_id.0 as i64);
+    let locale = request
+        .locale
+        .parse::<LanguageIdentifier>()
+        .unwrap_or_else(|_| LANGUAGE_LOADER.fallback_language().clone());
+    let loader = LANGUAGE_LOADER
+        .select_languages_negotiate(slice::from_ref(&locale), NegotiationStrategy::Filtering);
+
+    if join_request {
+        decline_join_request(bot, config, request.user_id).await;
+    }
+
+    let send_result = check_send_result(
+        bot.send_message(chat_id, fl!(loader, "request-expired"))
+            .await,
+    )?;
+
+    storage.clone().remove_dialogue(chat_id).await?;
+
+    let record = DecisionRecord {
+        request_id: request.request_id.clone(),
+        user_id: request.user_id,
+        outcome: Outcome::Expired,
+        // Expiry is not attributed to any moderator.
+        reviewer_id: UserId(0),
+        reviewer: String::new(),
+        send_result: send_result.as_str().to_string(),
+        decided_at: Utc::now(),
+    };
+
+    if let Err(err) = audit_log.record_decision(&record).await {
+        error!("failed to record request expiry: {err}");
+    }
+
+    let moderator_loader = config.moderator_loader();
+    let mut footer = fl!(moderator_loader, "moderator-expired");
+
+    if let Some(note) = send_result_note(&moderator_loader, &send_result) {
+        footer.push_str(&format!("\n\n{note}"));
+    }
+
+    if let Err(err) = annotate_review(bot, config, audit_log, &request.request_id, &footer).await {
+        warn!("failed to mark review {} as expired: {err}", message_id.0);
+    }
+
+    info!(user_id:debug = request.user_id; "Join request expired");
+
+    Ok(())
+}
+
+fn format_hours(loader: &FluentLanguageLoader, delta: TimeDelta) -> String {
+    fl!(loader, "moderator-hours", hours = delta.num_hours())
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::response::Json;
+use axum::routing::post;
+use chrono::Utc;
+use serde_json::{Value, json};
+use teloxide::prelude::*;
+use tokio::net::TcpListener;
+
+pub const BOT_ID: u64 = 1000;
+
+/// A call the bot made to the Bot API, along with the result it was given.
+#[derive(Clone, Debug)]
+pub struct Call {
+    pub method: String,
+    pub params: Value,
+    pub result: Value,
+}
+
+#[derive(Default)]
+struct Inner {
+    calls: Mutex<Vec<Call>>,
+    responses: Mutex<HashMap<String, Value>>,
+    next_message_id: AtomicI32,
+}
+
+/// A local stand-in for the Telegram Bot API which records every call and answers with plausible
+/// results, so that handlers can be run end to end.
+pub struct FakeBotApi {
+    url: reqwest::Url,
+    inner: Arc<Inner>,
+}
+
+impl FakeBotApi {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap())
+            .parse()
+            .unwrap();
+        let inner = Arc::new(Inner {
+            next_message_id: AtomicI32::new(100),
+            ..Default::default()
+        });
+
+        let app = Router::new()
+            .route("/{token}/{method}", post(handle))
+            .with_state(inner.clone());
+
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self { url, inner }
+    }
+
+    pub fn bot(&self) -> Bot {
+        Bot::new(format!("{BOT_ID}:TEST")).set_api_url(self.url.clone())
+    }
+
+    /// Overrides the result of a method, e.g. to report an applicant as a member.
+    pub fn respond(&self, method: &str, result: Value) {
+        self.inner
+            .responses
+            .lock()
+            .unwrap()
+            .insert(method.to_string(), result);
+    }
+
+    /// Returns and forgets all calls made so far.
+    pub fn take_calls(&self) -> Vec<Call> {
+        std::mem::take(&mut self.inner.calls.lock().unwrap())
+    }
+}
+
+async fn handle(
+    State(inner): State<Arc<Inner>>,
+    Path((_token, method)): Path<(String, String)>,
+    body: Bytes,
+) -> Json<Value> {
+    // teloxide names methods in PascalCase, while the Bot API documents them in camelCase.
+    let method = method[..1].to_lowercase() + &method[1..];
+
+    // Uploads are sent as multipart forms, whose parameters are not recorded.
+    let params = serde_json::from_slice(&body).unwrap_or(Value::Null);
+
+    let result = match inner.responses.lock().unwrap().get(&method) {
+        Some(result) => result.clone(),
+        None => default_result(&inner, &method, &params),
+    };
+
+    inner.calls.lock().unwrap().push(Call {
+        method,
+        params,
+        result: result.clone(),
+    });
+
+    Json(json!({ "ok": true, "result": result }))
+}
+
+fn default_result(inner: &Inner, method: &str, params: &Value) -> Value {
+    match method {
+        "getMe" => json!({
+            "id": BOT_ID,
+            "is_bot": true,
+            "first_name": "Bouncer",
+            "username": "bouncer_bot",
+            "can_join_groups": true,
+            "can_read_all_group_messages": false,
+            "supports_inline_queries": false,
+            "has_main_web_app": false,
+        }),
+        "getChatMember" => json!({
+            "status": "left",
+            "user": user(params["user_id"].as_u64().unwrap(), "Applicant"),
+        }),
+        "createChatInviteLink" => json!({
+            "invite_link": "https://t.me/+fake",
+            "creator": user(BOT_ID, "Bouncer"),
+            "creates_join_request": false,
+            "is_primary": false,
+            "is_revoked": false,
+            "name": params["name"],
+            "member_limit": params["member_limit"],
+        }),
+        "sendMessage" | "forwardMessage" => {
+            let message_id = inner.next_message_id.fetch_add(1, Ordering::Relaxed);
+            message(message_id, &params["chat_id"], params)
+        }
+        "editMessageText" | "editMessageReplyMarkup" => message(
+            params["message_id"].as_i64().unwrap() as i32,
+            &params["chat_id"],
+            params,
+        ),
+        _ => json!(true),
+    }
+}
+
+pub fn user(id: u64, first_name: &str) -> Value {
+    json!({
+        "id": id,
+        "is_bot": id == BOT_ID,
+        "first_name": first_name,
+        "language_code": "en",
+    })
+}
+
+pub fn chat(id: i64) -> Value {
+    match id {
+        id if id > 0 => json!({ "id": id, "type": "private", "first_name": "Applicant" }),
+        id => json!({ "id": id, "type": "supergroup", "title": "Group" }),
+    }
+}
+
+fn message(message_id: i32, chat_id: &Value, params: &Value) -> Value {
+    let mut message = json!({
+        "message_id": message_id,
+        "date": Utc::now().timestamp(),
+        "chat": chat(chat_id.as_i64().unwrap()),
+        "from": user(BOT_ID, "Bouncer"),
+        "text": params["text"].as_str().unwrap_or_default(),
+    });
+
+    if !params["reply_markup"].is_null() {
+        message["reply_markup"] = params["reply_markup"].clone();
+    }
+
+    message
+}
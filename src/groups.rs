@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use i18n_embed::fluent::{FluentLanguageLoader, NegotiationStrategy};
+use i18n_embed_fl::fl;
+use log::warn;
+use serde::Deserialize;
+use teloxide::dispatching::dialogue::serializer::Json;
+use teloxide::dispatching::dialogue::{InMemStorage, SqliteStorage, Storage};
+use teloxide::prelude::*;
+use teloxide::types::{Me, UpdateKind};
+use teloxide::utils::command::BotCommands;
+
+use crate::pin_history::PinHistory;
+use crate::rules::Rules;
+use crate::{
+    Command, Config, JoinStorage, LANGUAGE_LOADER, State, decline_join_request, locale_from_user,
+};
+
+/// A further group served by the bot, which applicants select through the link to the bot, e.g.
+/// `t.me/<bot>?start=<name>`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub primary_chat_id: i64,
+    pub moderator_chat_id: i64,
+    pub channel_id: Option<i64>,
+    /// Replaces the join questions of the default group.
+    pub join_questions: Option<Vec<String>>,
+    /// Replaces the rules of the default group.
+    pub rules_message: Option<String>,
+}
+
+/// The further groups, in addition to the default group configured through the top-level
+/// settings.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Groups(pub Vec<Group>);
+
+impl FromStr for Groups {
+    type Err = serde_json::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        serde_json::from_str(value).map(Self)
+    }
+}
+
+/// Checks a group name against the characters Telegram allows in the payload of a link.
+pub fn is_valid_group_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+}
+
+/// Returns the configuration as seen by each group, starting with the default group.
+pub fn group_configs(config: &Arc<Config>) -> Vec<Arc<Config>> {
+    let mut configs = vec![config.clone()];
+    configs.extend(
+        config
+            .groups()
+            .iter()
+            .map(|group| Arc::new(config.for_group(group))),
+    );
+    configs
+}
+
+/// What a group keeps apart from the other groups, so that applicants can apply to several of
+/// them independently.
+#[derive(Clone)]
+pub struct GroupState {
+    pub storage: JoinStorage,
+    pub pin_history: Arc<PinHistory>,
+    pub rules: Arc<Rules>,
+}
+
+/// The state of every group, keyed by its primary chat.
+pub struct GroupStates(HashMap<ChatId, GroupState>);
+
+impl GroupStates {
+    /// Opens the state of every group, with the default group using the files of a bot serving
+    /// a single group.
+    pub async fn open(config: &Config, rules: Rules) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let rules = Arc::new(rules);
+        let mut states = HashMap::new();
+
+        states.insert(
+            ChatId(config.primary_chat_id),
+            GroupState {
+                storage: open_storage(config, "dialogues.sqlite").await?,
+                pin_history: Arc::new(open_pin_history(config, "pinned_messages.json")?),
+                rules: rules.clone(),
+            },
+        );
+
+        for group in config.groups() {
+            let rules = match &group.rules_message {
+                Some(message_id) => Arc::new(Rules::Message(message_id.clone())),
+                None => rules.clone(),
+            };
+
+            states.insert(
+                ChatId(group.primary_chat_id),
+                GroupState {
+                    storage: open_storage(config, &format!("dialogues-{}.sqlite", group.name))
+                        .await?,
+                    pin_history: Arc::new(open_pin_history(
+                        config,
+                        &format!("pinned_messages-{}.json", group.name),
+                    )?),
+                    rules,
+                },
+            );
+        }
+
+        Ok(Self(states))
+    }
+
+    pub fn get(&self, primary_chat_id: ChatId) -> Option<&GroupState> {
+        self.0.get(&primary_chat_id)
+    }
+}
+
+async fn open_storage(
+    config: &Config,
+    file_name: &str,
+) -> Result<JoinStorage, Box<dyn Error + Send + Sync>> {
+    Ok(match config.storage_path.as_deref() {
+        Some(storage_path) => SqliteStorage::open(path_str(&storage_path.join(file_name))?, Json)
+            .await?
+            .erase(),
+        None => InMemStorage::new().erase(),
+    })
+}
+
+fn open_pin_history(config: &Config, file_name: &str) -> std::io::Result<PinHistory> {
+    match (
+        config.max_pinned_channel_posts,
+        config.storage_path.as_deref(),
+    ) {
+        (Some(max), Some(storage_path)) => PinHistory::load(storage_path.join(file_name), max),
+        _ => Ok(PinHistory::disabled()),
+    }
+}
+
+fn path_str(path: &Path) -> Result<&str, Box<dyn Error + Send + Sync>> {
+    path.to_str()
+        .ok_or_else(|| format!("{} is not valid UTF-8", path.display()).into())
+}
+
+/// The group an update belongs to, as provided to the handlers.
+#[derive(Clone)]
+pub struct SelectedGroup {
+    pub config: Arc<Config>,
+    pub state: GroupState,
+}
+
+/// Returns the payload of a `/start` command, which is empty when the bot was opened without
+/// selecting a group.
+pub fn start_payload(msg: &Message, me: &Me) -> Option<String> {
+    match Command::parse(msg.text()?, me.username()) {
+        Ok(Command::Start(payload)) => Some(payload.trim().to_string()),
+        _ => None,
+    }
+}
+
+/// Selects the group an update belongs to. Updates from group chats are matched by their chat,
+/// while private chats with applicants are matched by the group they are applying to.
+pub async fn select_group(
+    bot: Bot,
+    update: Update,
+    me: Me,
+    config: Arc<Config>,
+    states: Arc<GroupStates>,
+) -> Option<SelectedGroup> {
+    let configs = group_configs(&config);
+
+    let index = match update.chat() {
+        Some(chat) if chat.is_private() => {
+            let payload = match &update.kind {
+                UpdateKind::Message(msg) => start_payload(msg, &me),
+                _ => None,
+            };
+
+            match payload {
+                Some(payload) => {
+                    let index = if payload.is_empty() {
+                        0
+                    } else {
+                        // The default group comes first, followed by the further groups in order.
+                        config
+                            .groups()
+                            .iter()
+                            .position(|group| group.name == payload)?
+                            + 1
+                    };
+
+                    let loader = LANGUAGE_LOADER.select_languages_negotiate(
+                        &[locale_from_user(update.from())],
+                        NegotiationStrategy::Filtering,
+                    );
+                    abandon_other_applications(&bot, &loader, (&configs, &states), chat.id, index)
+                        .await;
+                    index
+                }
+                None => applying_to(&configs, &states, chat.id).await,
+            }
+        }
+        // Chats which belong to no group are left alone, rather than being mistaken for the
+        // default group.
+        Some(chat) => configs.iter().position(|config| {
+            config.primary_chat_id == chat.id.0
+                || config.moderator_chat_id == chat.id.0
+                || config.channel_id == Some(chat.id.0)
+        })?,
+        None => 0,
+    };
+
+    let config = configs.into_iter().nth(index)?;
+    let state = states.get(ChatId(config.primary_chat_id))?.clone();
+
+    Some(SelectedGroup { config, state })
+}
+
+fn is_unfinished(state: &State) -> bool {
+    matches!(
+        state,
+        State::ReceiveReason | State::AcceptRules { .. } | State::ReceiveAnswer { .. }
+    )
+}
+
+/// How strongly a dialogue ties the applicant's messages to its group.
+fn priority(state: &State) -> u8 {
+    match state {
+        state if is_unfinished(state) => 3,
+        State::AwaitApproval { .. } => 2,
+        State::Blocked => 1,
+        _ => 0,
+    }
+}
+
+/// Returns the group whose join flow the applicant is going through, as their messages cannot
+/// name a group themselves.
+async fn applying_to(configs: &[Arc<Config>], states: &GroupStates, chat_id: ChatId) -> usize {
+    let mut selected = (0, 0);
+
+    for (index, config) in configs.iter().enumerate() {
+        let Some(state) = states.get(ChatId(config.primary_chat_id)) else {
+            continue;
+        };
+
+        if let Ok(Some(dialogue)) = state.storage.clone().get_dialogue(chat_id).await
+            && priority(&dialogue) > selected.1
+        {
+            selected = (index, priority(&dialogue));
+        }
+    }
+
+    selected.0
+}
+
+/// Cancels unfinished join flows for other groups, as the answers could not be told apart. Join
+/// requests of those flows are declined, so that they do not stay pending without a review.
+async fn abandon_other_applications(
+    bot: &Bot,
+    loader: &FluentLanguageLoader,
+    (configs, states): (&[Arc<Config>], &GroupStates),
+    chat_id: ChatId,
+    index: usize,
+) {
+    let mut abandoned = false;
+
+    for (other, config) in configs.iter().enumerate() {
+        let Some(state) = states.get(ChatId(config.primary_chat_id)) else {
+            continue;
+        };
+
+        if other != index
+            && let Ok(Some(dialogue)) = state.storage.clone().get_dialogue(chat_id).await
+            && is_unfinished(&dialogue)
+        {
+            if let State::AcceptRules { join_request: true }
+            | State::ReceiveAnswer {
+                join_request: true, ..
+            } = dialogue
+            {
+                decline_join_request(bot, config, UserId(chat_id.0 as u64)).await;
+            }
+
+            let _ = state.storage.clone().remove_dialogue(chat_id).await;
+            abandoned = true;
+        }
+    }
+
+    if abandoned
+        && let Err(err) = bot
+            .send_message(chat_id, fl!(loader, "application-abandoned"))
+            .await
+    {
+        warn!("failed to tell {chat_id} about their abandoned application: {err}");
+    }
+}
@@ -0,0 +1,685 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use dptree::di::DependencyMap;
+use serde_json::{Value, json};
+use teloxide::types::Update;
+
+use super::*;
+use crate::fake_bot_api::{Call, FakeBotApi, chat, user};
+
+const PRIMARY_CHAT_ID: i64 = -100;
+const MODERATOR_CHAT_ID: i64 = -200;
+const CHANNEL_ID: i64 = -300;
+const APPLICANT_ID: u64 = 42;
+const MODERATOR_ID: u64 = 7;
+
+/// Runs updates through the real handler schema, with the Bot API replaced by [`FakeBotApi`].
+struct Harness {
+    api: FakeBotApi,
+    deps: DependencyMap,
+    next_update_id: AtomicI32,
+}
+
+impl Harness {
+    async fn new(env: &[(&str, &str)]) -> Self {
+        let mut vars = HashMap::from([
+            ("PRIMARY_CHAT_ID".to_string(), PRIMARY_CHAT_ID.to_string()),
+            (
+                "MODERATOR_CHAT_ID".to_string(),
+                MODERATOR_CHAT_ID.to_string(),
+            ),
+            ("CHANNEL_ID".to_string(), CHANNEL_ID.to_string()),
+            ("UNDO_WINDOW_SECONDS".to_string(), "0".to_string()),
+        ]);
+        vars.extend(
+            env.iter()
+                .map(|(key, value)| (key.to_string(), value.to_string())),
+        );
+        let config = Config::init_from_hashmap(&vars).unwrap();
+
+        let api = FakeBotApi::start().await;
+        let bot = api.bot();
+        let me = bot.get_me().await.unwrap();
+        api.take_calls();
+
+        let group_states = GroupStates::open(&config, Rules::Disabled).await.unwrap();
+        let banlist = Arc::new(Banlist::in_memory());
+        let countersign = Arc::new(Countersign::new(
+            Vec::new(),
+            banlist.clone(),
+            config.banlist_action,
+        ));
+        let audit_log = Arc::new(AuditLog::open(None).await.unwrap());
+
+        let mut deps = dptree::deps![
+            Arc::new(group_states),
+            Arc::new(ConfigHandle::new(Arc::new(config))),
+            countersign,
+            audit_log,
+            banlist,
+            Arc::new(RequestLocks::default())
+        ];
+        deps.insert(bot);
+        deps.insert(me);
+
+        Self {
+            api,
+            deps,
+            next_update_id: AtomicI32::new(1),
+        }
+    }
+
+    /// Handles an update the way the dispatcher would and returns the calls it caused.
+    async fn dispatch(&self, kind: &str, payload: Value) -> Vec<Call> {
+        self.try_dispatch(kind, payload)
+            .await
+            .expect("update was not handled")
+    }
+
+    /// Handles an update like [`Harness::dispatch`], returning `None` if no handler accepted it.
+    async fn try_dispatch(&self, kind: &str, payload: Value) -> Option<Vec<Call>> {
+        let update_id = self.next_update_id.fetch_add(1, Ordering::Relaxed);
+        let mut update = json!({ "update_id": update_id });
+        update[kind] = payload;
+        // teloxide only reads update kinds with borrowed keys, which a `Value` cannot lend.
+        let update: Update = serde_json::from_str(&update.to_string()).unwrap();
+
+        let mut deps = self.deps.clone();
+        deps.insert(update);
+
+        match schema().dispatch(deps).await {
+            ControlFlow::Break(result) => result.unwrap(),
+            ControlFlow::Continue(_) => return None,
+        }
+
+        Some(self.api.take_calls())
+    }
+
+    async fn send_private_message(&self, text: &str) -> Vec<Call> {
+        self.dispatch(
+            "message",
+            json!({
+                "message_id": 1,
+                "date": Utc::now().timestamp(),
+                "chat": chat(APPLICANT_ID as i64),
+                "from": user(APPLICANT_ID, "Applicant"),
+                "text": text,
+            }),
+        )
+        .await
+    }
+
+    /// Sends a command to the moderator chat, optionally in reply to a message the bot sent.
+    async fn send_moderator_command(&self, text: &str, reply_to: Option<&Value>) -> Vec<Call> {
+        let mut message = json!({
+            "message_id": 1,
+            "date": Utc::now().timestamp(),
+            "chat": chat(MODERATOR_CHAT_ID),
+            "from": user(MODERATOR_ID, "Moderator"),
+            "text": text,
+        });
+
+        if let Some(reply_to) = reply_to {
+            message["reply_to_message"] = reply_to.clone();
+        }
+
+        self.dispatch("message", message).await
+    }
+
+    /// Presses the button with the given label on a message the bot sent.
+    async fn press_button(&self, message: &Value, label: &str) -> Vec<Call> {
+        self.press_button_as(message, label, user(MODERATOR_ID, "Moderator"))
+            .await
+    }
+
+    /// Presses a button like [`Harness::press_button`], as the given moderator.
+    async fn press_button_as(&self, message: &Value, label: &str, moderator: Value) -> Vec<Call> {
+        let data = message["reply_markup"]["inline_keyboard"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .flat_map(|row| row.as_array().unwrap())
+            .find(|button| button["text"] == label)
+            .unwrap_or_else(|| panic!("no \"{label}\" button"))["callback_data"]
+            .clone();
+
+        self.dispatch(
+            "callback_query",
+            json!({
+                "id": "1",
+                "from": moderator,
+                "chat_instance": "1",
+                "message": message,
+                "data": data,
+            }),
+        )
+        .await
+    }
+
+    /// Applies with the given reason and returns the review message posted to the moderators.
+    async fn apply(&self, reason: &str) -> Value {
+        self.apply_to("/start", MODERATOR_CHAT_ID, reason).await
+    }
+
+    /// Applies through the given start command to the group with the given moderator chat.
+    async fn apply_to(&self, start: &str, moderator_chat_id: i64, reason: &str) -> Value {
+        self.send_private_message(start).await;
+        let calls = self.send_private_message(reason).await;
+
+        find_call(&calls, "sendMessage", moderator_chat_id)
+            .result
+            .clone()
+    }
+}
+
+/// Returns a message as the applicants, who all speak English, receive it.
+fn english(message_id: &str) -> String {
+    LANGUAGE_LOADER
+        .select_languages_negotiate(
+            &["en".parse::<LanguageIdentifier>().unwrap()],
+            NegotiationStrategy::Filtering,
+        )
+        .get(message_id)
+}
+
+fn methods(calls: &[Call]) -> Vec<&str> {
+    calls.iter().map(|call| call.method.as_str()).collect()
+}
+
+fn find_call<'a>(calls: &'a [Call], method: &str, chat_id: i64) -> &'a Call {
+    calls
+        .iter()
+        .find(|call| call.method == method && call.params["chat_id"] == chat_id)
+        .unwrap_or_else(|| panic!("no {method} call for chat {chat_id} in {calls:#?}"))
+}
+
+#[tokio::test]
+async fn start_asks_for_a_reason() {
+    let harness = Harness::new(&[]).await;
+
+    let calls = harness.send_private_message("/start").await;
+
+    assert_eq!(methods(&calls), ["getChatMember", "sendMessage"]);
+    assert_eq!(calls[1].params["text"], english("reason-prompt"));
+}
+
+#[tokio::test]
+async fn start_tells_members_they_are_already_in() {
+    let harness = Harness::new(&[]).await;
+    harness.api.respond(
+        "getChatMember",
+        json!({ "status": "member", "user": user(APPLICANT_ID, "Applicant") }),
+    );
+
+    let calls = harness.send_private_message("/start").await;
+
+    assert_eq!(methods(&calls), ["getChatMember", "sendMessage"]);
+    assert_eq!(calls[1].params["text"], english("present-member"));
+}
+
+#[tokio::test]
+async fn reason_is_posted_for_review() {
+    let harness = Harness::new(&[]).await;
+    harness.send_private_message("/start").await;
+
+    let calls = harness.send_private_message("I like bouncers").await;
+
+    assert_eq!(
+        methods(&calls),
+        ["getChatMember", "sendMessage", "sendMessage"]
+    );
+
+    let review = find_call(&calls, "sendMessage", MODERATOR_CHAT_ID);
+    assert!(
+        review.params["text"]
+            .as_str()
+            .unwrap()
+            .contains("I like bouncers")
+    );
+    assert_eq!(
+        review.params["reply_markup"]["inline_keyboard"][0][0]["text"],
+        "Approve"
+    );
+
+    let confirmation = find_call(&calls, "sendMessage", APPLICANT_ID as i64);
+    assert_eq!(confirmation.params["text"], english("reason-received"));
+}
+
+#[tokio::test]
+async fn approving_sends_an_invite_link() {
+    let harness = Harness::new(&[]).await;
+    let review = harness.apply("I like bouncers").await;
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(
+        methods(&calls),
+        [
+            "answerCallbackQuery",
+            "createChatInviteLink",
+            "sendMessage",
+            "editMessageText"
+        ]
+    );
+    assert_eq!(calls[1].params["chat_id"], PRIMARY_CHAT_ID);
+    assert_eq!(calls[1].params["member_limit"], 1);
+    assert!(
+        calls[2].params["text"]
+            .as_str()
+            .unwrap()
+            .contains("https://t.me/+fake")
+    );
+    assert_eq!(calls[3].params["chat_id"], MODERATOR_CHAT_ID);
+    assert_eq!(calls[3].params["message_id"], review["message_id"]);
+    assert!(
+        calls[3].params["text"]
+            .as_str()
+            .unwrap()
+            .contains("Approved by Moderator")
+    );
+}
+
+#[tokio::test]
+async fn second_click_is_rejected() {
+    let harness = Harness::new(&[]).await;
+    let review = harness.apply("I like bouncers").await;
+    harness.press_button(&review, "Approve").await;
+
+    let calls = harness.press_button(&review, "Deny").await;
+
+    assert_eq!(methods(&calls), ["answerCallbackQuery"]);
+    assert_eq!(calls[0].params["show_alert"], true);
+}
+
+#[tokio::test]
+async fn commands_do_not_redecide_requests() {
+    let harness = Harness::new(&[]).await;
+    let review = harness.apply("I like bouncers").await;
+    harness.press_button(&review, "Approve").await;
+
+    for command in [format!("/block {APPLICANT_ID}"), "/deny Spam".to_string()] {
+        let calls = harness
+            .send_moderator_command(&command, Some(&review))
+            .await;
+
+        assert_eq!(methods(&calls), ["sendMessage"]);
+        assert_eq!(calls[0].params["chat_id"], MODERATOR_CHAT_ID);
+        assert!(
+            calls[0].params["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("This join request was already decided")
+        );
+    }
+}
+
+#[tokio::test]
+async fn approve_command_uses_the_stored_request() {
+    let harness = Harness::new(&[]).await;
+
+    let calls = harness
+        .send_moderator_command(&format!("/approve {APPLICANT_ID}"), None)
+        .await;
+
+    assert_eq!(methods(&calls), ["sendMessage"]);
+    assert_eq!(
+        calls[0].params["text"],
+        format!("There is no pending join request from {APPLICANT_ID}")
+    );
+
+    harness.apply("I like bouncers").await;
+
+    let calls = harness
+        .send_moderator_command(&format!("/approve {APPLICANT_ID}"), None)
+        .await;
+
+    assert_eq!(calls[0].method, "createChatInviteLink");
+    assert!(
+        find_call(&calls, "sendMessage", APPLICANT_ID as i64).params["text"]
+            .as_str()
+            .unwrap()
+            .contains("https://t.me/+fake")
+    );
+}
+
+#[tokio::test]
+async fn command_replies_cannot_be_undone() {
+    let harness = Harness::new(&[("UNDO_WINDOW_SECONDS", "60")]).await;
+    harness.apply("I like bouncers").await;
+
+    let calls = harness
+        .send_moderator_command(&format!("/approve {APPLICANT_ID}"), None)
+        .await;
+
+    let reply = find_call(&calls, "sendMessage", MODERATOR_CHAT_ID);
+    assert!(!reply.params["reply_markup"].to_string().contains("Undo"));
+}
+
+#[tokio::test]
+async fn blocked_users_are_only_unblocked() {
+    let harness = Harness::new(&[]).await;
+    let review = harness.apply("I like bouncers").await;
+    harness.press_button(&review, "Block").await;
+
+    let calls = harness
+        .send_moderator_command(&format!("/approve {APPLICANT_ID}"), None)
+        .await;
+
+    assert_eq!(methods(&calls), ["sendMessage"]);
+    assert_eq!(calls[0].params["chat_id"], MODERATOR_CHAT_ID);
+
+    let calls = harness
+        .send_moderator_command(&format!("/unblock {APPLICANT_ID}"), None)
+        .await;
+
+    assert_eq!(
+        find_call(&calls, "sendMessage", APPLICANT_ID as i64).params["text"],
+        english("unblocked")
+    );
+}
+
+#[tokio::test]
+async fn denied_scammers_are_neither_told_nor_pending() {
+    let harness = Harness::new(&[("BANLIST_ACTION", "deny")]).await;
+    harness
+        .deps
+        .get::<Arc<Banlist>>()
+        .add(BanlistEntry {
+            user_id: UserId(APPLICANT_ID),
+            reason: None,
+            added_by: None,
+            added_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+    harness.send_private_message("/start").await;
+
+    let calls = harness.send_private_message("I like bouncers").await;
+
+    assert_eq!(methods(&calls), ["getChatMember", "sendMessage"]);
+    assert_eq!(calls[1].params["chat_id"], MODERATOR_CHAT_ID);
+
+    let calls = harness.send_moderator_command("/pending", None).await;
+
+    assert_eq!(calls[0].params["text"], "There are no open join requests");
+}
+
+#[tokio::test]
+async fn approvals_wait_for_the_quorum() {
+    let harness = Harness::new(&[("APPROVAL_QUORUM", "2")]).await;
+    let review = harness.apply("I like bouncers").await;
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert!(!methods(&calls).contains(&"createChatInviteLink"));
+    assert!(!methods(&calls).contains(&"sendMessage"));
+
+    let calls = harness
+        .press_button_as(&review, "Approve", user(MODERATOR_ID + 1, "Other"))
+        .await;
+
+    assert!(
+        find_call(&calls, "sendMessage", APPLICANT_ID as i64).params["text"]
+            .as_str()
+            .unwrap()
+            .contains("https://t.me/+fake")
+    );
+
+    let audit_log = harness.deps.get::<Arc<AuditLog>>();
+    let request_id = audit_log
+        .latest_request_id(ChatId(PRIMARY_CHAT_ID), UserId(APPLICANT_ID))
+        .await
+        .unwrap()
+        .unwrap();
+    let decision = audit_log
+        .decisions(&request_id)
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert!(matches!(
+        decision.outcome,
+        Outcome::Review(ReviewAction::Approve)
+    ));
+    assert_eq!(decision.reviewer, "Moderator, Other");
+}
+
+#[tokio::test]
+async fn tallies_replace_those_in_other_languages() {
+    let harness = Harness::new(&[("APPROVAL_QUORUM", "2")]).await;
+    let mut review = harness.apply("I like bouncers").await;
+    // A tally posted before the moderator locale was changed.
+    review["text"] = format!("{}\n\nStimmen:", review["text"].as_str().unwrap()).into();
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    let text = find_call(&calls, "editMessageText", MODERATOR_CHAT_ID).params["text"]
+        .as_str()
+        .unwrap();
+    assert!(!text.contains("Stimmen"));
+    assert_eq!(text.matches(&english("moderator-votes")).count(), 1);
+}
+
+#[tokio::test]
+async fn votes_without_a_dialogue_are_rejected() {
+    let harness = Harness::new(&[("APPROVAL_QUORUM", "2")]).await;
+    let review = harness.apply("I like bouncers").await;
+    harness
+        .deps
+        .get::<Arc<GroupStates>>()
+        .get(ChatId(PRIMARY_CHAT_ID))
+        .unwrap()
+        .storage
+        .clone()
+        .remove_dialogue(ChatId(APPLICANT_ID as i64))
+        .await
+        .unwrap();
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(methods(&calls), ["answerCallbackQuery", "sendMessage"]);
+    assert_eq!(calls[1].params["chat_id"], MODERATOR_CHAT_ID);
+    assert_eq!(calls[1].params["text"], english("moderator-votes-lost"));
+}
+
+#[tokio::test]
+async fn join_requests_are_approved_directly() {
+    let harness = Harness::new(&[]).await;
+
+    let calls = harness
+        .dispatch(
+            "chat_join_request",
+            json!({
+                "chat": chat(PRIMARY_CHAT_ID),
+                "from": user(APPLICANT_ID, "Applicant"),
+                "user_chat_id": APPLICANT_ID,
+                "date": Utc::now().timestamp(),
+            }),
+        )
+        .await;
+
+    assert_eq!(methods(&calls), ["sendMessage"]);
+    assert_eq!(calls[0].params["text"], english("join-request-prompt"));
+
+    let calls = harness.send_private_message("I like bouncers").await;
+    let review = find_call(&calls, "sendMessage", MODERATOR_CHAT_ID)
+        .result
+        .clone();
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(
+        methods(&calls),
+        [
+            "answerCallbackQuery",
+            "approveChatJoinRequest",
+            "sendMessage",
+            "editMessageText"
+        ]
+    );
+    assert_eq!(calls[1].params["user_id"], APPLICANT_ID);
+}
+
+#[tokio::test]
+async fn outdated_buttons_are_rejected() {
+    let harness = Harness::new(&[]).await;
+    let review = json!({
+        "message_id": 5,
+        "date": Utc::now().timestamp(),
+        "chat": chat(MODERATOR_CHAT_ID),
+        "text": "Applicant would like to join",
+        "reply_markup": {
+            "inline_keyboard": [[{ "text": "Approve", "callback_data": "AQ" }]],
+        },
+    });
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(methods(&calls), ["answerCallbackQuery"]);
+    assert_eq!(calls[0].params["show_alert"], true);
+}
+
+#[tokio::test]
+async fn channel_posts_are_forwarded_and_pinned() {
+    let harness = Harness::new(&[]).await;
+
+    let calls = harness
+        .dispatch(
+            "channel_post",
+            json!({
+                "message_id": 5,
+                "date": Utc::now().timestamp(),
+                "chat": { "id": CHANNEL_ID, "type": "channel", "title": "Channel" },
+                "text": "News",
+            }),
+        )
+        .await;
+
+    assert_eq!(methods(&calls), ["forwardMessage", "pinChatMessage"]);
+    assert_eq!(calls[0].params["chat_id"], PRIMARY_CHAT_ID);
+    assert_eq!(calls[0].params["from_chat_id"], CHANNEL_ID);
+    assert_eq!(calls[0].params["message_id"], 5);
+    assert_eq!(calls[1].params["chat_id"], PRIMARY_CHAT_ID);
+    assert_eq!(calls[1].params["message_id"], calls[0].result["message_id"]);
+}
+
+const GROUPS: &str = r#"[{
+    "name": "other",
+    "primary_chat_id": -101,
+    "moderator_chat_id": -201
+}]"#;
+
+#[tokio::test]
+async fn start_payload_selects_the_group() {
+    let harness = Harness::new(&[("GROUPS", GROUPS)]).await;
+
+    let review = harness
+        .apply_to("/start other", -201, "I like bouncers")
+        .await;
+
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(calls[1].method, "createChatInviteLink");
+    assert_eq!(calls[1].params["chat_id"], -101);
+    assert_eq!(calls[3].params["chat_id"], -201);
+}
+
+#[tokio::test]
+async fn applications_to_groups_are_independent() {
+    let harness = Harness::new(&[("GROUPS", GROUPS)]).await;
+    let review = harness.apply("I like bouncers").await;
+    let other_review = harness
+        .apply_to("/start other", -201, "I like bouncers too")
+        .await;
+
+    harness.press_button(&other_review, "Deny").await;
+    let calls = harness.press_button(&review, "Approve").await;
+
+    assert_eq!(calls[1].method, "createChatInviteLink");
+    assert_eq!(calls[1].params["chat_id"], PRIMARY_CHAT_ID);
+}
+
+#[tokio::test]
+async fn abandoned_join_requests_are_declined() {
+    let harness = Harness::new(&[("GROUPS", GROUPS)]).await;
+    harness
+        .dispatch(
+            "chat_join_request",
+            json!({
+                "chat": chat(PRIMARY_CHAT_ID),
+                "from": user(APPLICANT_ID, "Applicant"),
+                "user_chat_id": APPLICANT_ID,
+                "date": Utc::now().timestamp(),
+            }),
+        )
+        .await;
+
+    let calls = harness.send_private_message("/start other").await;
+
+    assert_eq!(calls[0].method, "declineChatJoinRequest");
+    assert_eq!(calls[0].params["chat_id"], PRIMARY_CHAT_ID);
+    assert_eq!(calls[1].params["text"], english("application-abandoned"));
+}
+
+#[tokio::test]
+async fn unknown_groups_are_rejected() {
+    let harness = Harness::new(&[("GROUPS", GROUPS)]).await;
+
+    let calls = harness.send_private_message("/start unknown").await;
+
+    assert_eq!(methods(&calls), ["sendMessage"]);
+    assert_eq!(calls[0].params["text"], english("group-unknown"));
+}
+
+#[tokio::test]
+async fn unknown_chats_are_ignored() {
+    let harness = Harness::new(&[("GROUPS", GROUPS)]).await;
+
+    let handled = harness
+        .try_dispatch(
+            "message",
+            json!({
+                "message_id": 1,
+                "date": Utc::now().timestamp(),
+                "chat": chat(-999),
+                "from": user(APPLICANT_ID, "Applicant"),
+                "text": "/start",
+            }),
+        )
+        .await;
+
+    assert!(handled.is_none());
+}
+
+#[tokio::test]
+async fn reviews_use_the_moderator_locale() {
+    let harness = Harness::new(&[("MODERATOR_LOCALE", "de")]).await;
+    let review = harness.apply("I like bouncers").await;
+
+    assert!(
+        review["text"]
+            .as_str()
+            .unwrap()
+            .contains("möchte aus folgendem Grund beitreten")
+    );
+
+    let calls = harness.press_button(&review, "Genehmigen").await;
+
+    let confirmation = find_call(&calls, "sendMessage", APPLICANT_ID as i64);
+    assert!(
+        confirmation.params["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("Your request has been approved")
+    );
+
+    let edit = find_call(&calls, "editMessageText", MODERATOR_CHAT_ID);
+    assert!(
+        edit.params["text"]
+            .as_str()
+            .unwrap()
+            .contains("Genehmigt von Moderator")
+    );
+}
@@ -0,0 +1,99 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use chrono::{TimeDelta, Utc};
+use log::{error, warn};
+use teloxide::prelude::*;
+use tokio::net::TcpListener;
+
+use crate::metrics::METRICS;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long Telegram may go without a successful call before the bot is considered unhealthy,
+/// which allows for a few failed probes in a row.
+const TELEGRAM_STALE_AFTER: TimeDelta = TimeDelta::minutes(5);
+
+/// Serves `/healthz` and `/metrics`, while probing Telegram in the background so that the health
+/// check does not depend on how busy the bot is.
+pub async fn serve(listener: TcpListener, bot: Bot, storage_path: Option<PathBuf>) {
+    tokio::spawn(probe_telegram(bot));
+
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics))
+        .with_state(Arc::new(storage_path));
+
+    if let Err(err) = axum::serve(listener, app).await {
+        error!("health server stopped: {err}");
+    }
+}
+
+async fn probe_telegram(bot: Bot) {
+    let mut interval = tokio::time::interval(PROBE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match bot.get_me().await {
+            Ok(_) => METRICS.record_telegram_success(),
+            Err(err) => warn!("failed to reach Telegram: {err}"),
+        }
+    }
+}
+
+async fn healthz(State(storage_path): State<Arc<Option<PathBuf>>>) -> impl IntoResponse {
+    let dispatcher = METRICS.is_dispatching();
+
+    let storage = match storage_path.as_ref() {
+        Some(storage_path) => {
+            let probe_path = storage_path.join(".healthz");
+            let result = match tokio::fs::write(&probe_path, b"ok").await {
+                Ok(()) => tokio::fs::remove_file(&probe_path).await,
+                Err(err) => Err(err),
+            };
+
+            result.map_err(|err| err.to_string())
+        }
+        None => Ok(()),
+    };
+
+    let last_telegram_success = METRICS.last_telegram_success();
+    let telegram = last_telegram_success
+        .is_some_and(|last_success| Utc::now() - last_success <= TELEGRAM_STALE_AFTER);
+
+    let status = if dispatcher && storage.is_ok() && telegram {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    let body = format!(
+        "dispatcher: {}\nstorage: {}\nlast successful Telegram call: {}\n",
+        if dispatcher { "running" } else { "stopped" },
+        match &storage {
+            Ok(()) if storage_path.is_some() => "writable".to_string(),
+            Ok(()) => "in memory".to_string(),
+            Err(err) => format!("not writable ({err})"),
+        },
+        last_telegram_success.map_or("never".to_string(), |last_success| last_success
+            .format("%Y-%m-%d %H:%M:%S UTC")
+            .to_string()),
+    );
+
+    (status, body)
+}
+
+async fn metrics() -> impl IntoResponse {
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        METRICS.render(),
+    )
+}
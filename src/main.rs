@@ -1,14 +1,30 @@
+use std::cmp::Reverse;
+use std::convert::Infallible;
 use std::env;
 use std::error::Error;
+use std::net::SocketAddr;
 use std::ops::Add;
 use std::path::PathBuf;
 use std::slice;
+use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::countersign::Countersign;
+use crate::audit_log::{
+    AuditLog, DecisionRecord, HistoryEntry, InviteLinkRecord, Outcome, RequestRecord,
+    RequestStatus, ReviewMessage, UnusedInviteLinks, new_request_id,
+};
+use crate::banlist::{Banlist, BanlistEntry, ImportError};
+use crate::config::ConfigHandle;
+use crate::countersign::{Countersign, ScammerAction, SourceMatch, Sources};
+use crate::groups::{Group, GroupStates, Groups, SelectedGroup};
+use crate::metrics::METRICS;
 use crate::pin_history::PinHistory;
-use crate::review::{Review, ReviewAction};
-use chrono::{TimeDelta, Utc};
+use crate::review::{
+    DenyReason, RequestLocks, Review, ReviewAction, ReviewCallback, TryFromError, Vote,
+};
+use crate::rules::Rules;
+use crate::translations::Translations;
+use chrono::{DateTime, TimeDelta, Utc};
 use envconfig::Envconfig;
 use i18n_embed::LanguageLoader;
 use i18n_embed::fluent::{FluentLanguageLoader, NegotiationStrategy, fluent_language_loader};
@@ -20,27 +36,45 @@ use rust_embed::RustEmbed;
 use serde::{Deserialize, Serialize};
 use structured_logger::Builder;
 use structured_logger::async_json::new_writer;
-use teloxide::dispatching::dialogue::serializer::Json;
-use teloxide::dispatching::dialogue::{ErasedStorage, SqliteStorage, Storage};
-use teloxide::types::{MaybeInaccessibleMessage, MessageId, ParseMode, User};
+use teloxide::dispatching::dialogue::ErasedStorage;
+use teloxide::net::Download;
+use teloxide::types::{
+    AllowedUpdate, CallbackQueryId, ChatJoinRequest, ChatMemberUpdated, InputFile,
+    MaybeInaccessibleMessage, Me, MessageEntity, MessageId, ParseMode, ReplyParameters, User,
+};
+use teloxide::update_listeners::{UpdateListener, webhooks};
 use teloxide::utils::markdown::escape;
 use teloxide::{
     ApiError, RequestError,
-    dispatching::{UpdateHandler, dialogue, dialogue::InMemStorage},
+    dispatching::{UpdateHandler, dialogue},
     prelude::*,
-    types::{InlineKeyboardButton, InlineKeyboardMarkup},
+    types::{InlineKeyboardButton, InlineKeyboardButtonKind, InlineKeyboardMarkup},
     utils::command::BotCommands,
 };
 
+mod audit_log;
+mod banlist;
+mod config;
 mod countersign;
+mod expiry;
+#[cfg(test)]
+mod fake_bot_api;
+mod groups;
+#[cfg(test)]
+mod handler_tests;
+mod health;
+mod metrics;
 mod pin_history;
 mod review;
+mod rules;
+mod storage;
+mod translations;
 
 type JoinDialogue = Dialogue<State, ErasedStorage<State>>;
 type JoinStorage = Arc<ErasedStorage<State>>;
 type HandlerResult = Result<(), Box<dyn Error + Send + Sync>>;
 
-#[derive(Envconfig)]
+#[derive(Envconfig, Clone)]
 pub struct Config {
     #[envconfig(from = "PRIMARY_CHAT_ID")]
     pub primary_chat_id: i64,
@@ -56,6 +90,154 @@ pub struct Config {
 
     #[envconfig(from = "MAX_PINNED_CHANNEL_POSTS")]
     pub max_pinned_channel_posts: Option<usize>,
+
+    #[envconfig(from = "JOIN_QUESTIONS")]
+    pub join_questions: Option<CommaSeparated>,
+
+    #[envconfig(from = "RULES_MESSAGE")]
+    pub rules_message: Option<String>,
+
+    #[envconfig(from = "RULES_FILE")]
+    pub rules_file: Option<PathBuf>,
+
+    #[envconfig(from = "DENY_REASONS")]
+    pub deny_reasons: Option<CommaSeparated>,
+
+    #[envconfig(from = "APPROVAL_QUORUM", default = "1")]
+    pub approval_quorum: usize,
+
+    #[envconfig(from = "DENIAL_QUORUM", default = "1")]
+    pub denial_quorum: usize,
+
+    #[envconfig(from = "SCAMMER_LIST_SOURCES")]
+    pub scammer_list_sources: Option<Sources>,
+
+    #[envconfig(from = "BANLIST_ACTION", default = "label")]
+    pub banlist_action: ScammerAction,
+
+    #[envconfig(from = "INVITE_LINK_LIFETIME_HOURS", default = "24")]
+    pub invite_link_lifetime_hours: u32,
+
+    #[envconfig(from = "INVITE_LINK_MEMBER_LIMIT", default = "1")]
+    pub invite_link_member_limit: u32,
+
+    #[envconfig(from = "REQUEST_EXPIRY_HOURS")]
+    pub request_expiry_hours: Option<u32>,
+
+    #[envconfig(from = "REQUEST_REMINDER_HOURS")]
+    pub request_reminder_hours: Option<u32>,
+
+    #[envconfig(from = "UNDO_WINDOW_SECONDS", default = "60")]
+    pub undo_window_seconds: u32,
+
+    #[envconfig(from = "WEBHOOK_URL")]
+    pub webhook_url: Option<reqwest::Url>,
+
+    #[envconfig(from = "WEBHOOK_LISTEN_ADDRESS", default = "0.0.0.0:8080")]
+    pub webhook_listen_address: SocketAddr,
+
+    #[envconfig(from = "WEBHOOK_SECRET_TOKEN")]
+    pub webhook_secret_token: Option<String>,
+
+    #[envconfig(from = "METRICS_LISTEN_ADDRESS")]
+    pub metrics_listen_address: Option<SocketAddr>,
+
+    #[envconfig(from = "GROUPS")]
+    pub groups: Option<Groups>,
+
+    #[envconfig(from = "MODERATOR_LOCALE", default = "en")]
+    pub moderator_locale: LanguageIdentifier,
+}
+
+impl Config {
+    pub fn join_questions(&self) -> &[String] {
+        self.join_questions
+            .as_ref()
+            .map_or(&[], |questions| questions.0.as_slice())
+    }
+
+    pub fn deny_reasons(&self) -> &[String] {
+        self.deny_reasons
+            .as_ref()
+            .map_or(&[], |reasons| reasons.0.as_slice())
+    }
+
+    pub fn request_expiry(&self) -> Option<TimeDelta> {
+        self.request_expiry_hours
+            .map(|hours| TimeDelta::hours(hours.into()))
+    }
+
+    pub fn request_reminder(&self) -> Option<TimeDelta> {
+        self.request_reminder_hours
+            .map(|hours| TimeDelta::hours(hours.into()))
+    }
+
+    /// Returns the webhook options if updates are to be received through a webhook instead of long
+    /// polling.
+    pub fn webhook_options(&self) -> Option<webhooks::Options> {
+        let options =
+            webhooks::Options::new(self.webhook_listen_address, self.webhook_url.clone()?);
+
+        Some(match &self.webhook_secret_token {
+            Some(secret_token) => options.secret_token(secret_token.clone()),
+            // Telegram is then given a random token, which is verified all the same.
+            None => options,
+        })
+    }
+
+    pub fn undo_window(&self) -> Option<TimeDelta> {
+        (self.undo_window_seconds > 0).then(|| TimeDelta::seconds(self.undo_window_seconds.into()))
+    }
+
+    /// Returns the loader for messages to the moderator chat.
+    pub fn moderator_loader(&self) -> FluentLanguageLoader {
+        LANGUAGE_LOADER.select_languages_negotiate(
+            slice::from_ref(&self.moderator_locale),
+            NegotiationStrategy::Filtering,
+        )
+    }
+
+    pub fn groups(&self) -> &[Group] {
+        self.groups
+            .as_ref()
+            .map_or(&[], |groups| groups.0.as_slice())
+    }
+
+    /// Returns the configuration as seen by a further group, which shares all settings with the
+    /// default group except for its chats and join questions. Its rules are kept with its
+    /// [`groups::GroupState`].
+    pub fn for_group(&self, group: &Group) -> Config {
+        Config {
+            primary_chat_id: group.primary_chat_id,
+            moderator_chat_id: group.moderator_chat_id,
+            channel_id: group.channel_id,
+            join_questions: group
+                .join_questions
+                .clone()
+                .map(CommaSeparated)
+                .or_else(|| self.join_questions.clone()),
+            groups: None,
+            ..self.clone()
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CommaSeparated(pub Vec<String>);
+
+impl FromStr for CommaSeparated {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Ok(Self(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect(),
+        ))
+    }
 }
 
 #[derive(BotCommands, Clone)]
@@ -69,18 +251,69 @@ enum Command {
     #[command(description = "display privacy policy")]
     Privacy,
     #[command(description = "request a join link")]
-    Start,
+    Start(String),
     #[command(description = "cancel join request")]
     Cancel,
 }
 
+#[derive(BotCommands, Clone)]
+#[command(
+    rename_rule = "lowercase",
+    description = "These moderator commands are supported:"
+)]
+enum ModeratorCommand {
+    #[command(description = "list open join requests")]
+    Pending,
+    #[command(description = "approve a user ID")]
+    Approve(String),
+    #[command(description = "deny a review with a custom reason, in reply to it")]
+    Deny(String),
+    #[command(description = "block a user ID")]
+    Block(String),
+    #[command(description = "unblock a user ID")]
+    Unblock(String),
+    #[command(description = "add a user ID to the banlist, optionally followed by a reason")]
+    Report(String),
+    #[command(description = "remove a user ID from the banlist")]
+    Unreport(String),
+    #[command(description = "export the banlist as JSON")]
+    ExportBanlist,
+    #[command(description = "import a JSON banlist, in reply to the file")]
+    ImportBanlist,
+    #[command(description = "show review statistics")]
+    Stats,
+    #[command(description = "list past join requests of a user ID or @username")]
+    History(String),
+    #[command(description = "reload the configuration")]
+    Reload,
+}
+
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub enum State {
     #[default]
     Start,
+    /// Kept for dialogues which were started before the join flow carried any context.
     ReceiveReason,
+    AcceptRules {
+        join_request: bool,
+    },
+    /// Walks through the configured join questions, the current step being the number of
+    /// answers collected so far. Without any questions configured, the only answer is the
+    /// reason to join.
+    ReceiveAnswer {
+        join_request: bool,
+        #[serde(default)]
+        rules_accepted_at: Option<DateTime<Utc>>,
+        answers: Vec<String>,
+    },
     AwaitApproval {
         message_id: MessageId,
+        #[serde(default)]
+        join_request: bool,
+        #[serde(default)]
+        votes: Vec<Vote>,
+        #[serde(default)]
+        reminded_at: Option<DateTime<Utc>>,
     },
     Blocked,
 }
@@ -92,8 +325,9 @@ struct Localizations;
 static LANGUAGE_LOADER: Lazy<FluentLanguageLoader> = Lazy::new(|| {
     let loader: FluentLanguageLoader = fluent_language_loader!();
 
-    loader
-        .load_available_languages(&Localizations)
+    // Overrides are only known once the configuration is loaded, which then applies them.
+    Translations::read(&loader, None)
+        .and_then(|translations| translations.apply(&loader))
         .expect("Error while loading languages");
 
     loader
@@ -116,73 +350,274 @@ async fn main() {
         .load_languages(&Localizations, &[loader.fallback_language().clone()])
         .unwrap();
 
-    let config = Config::init_from_env().unwrap();
-
-    let storage: JoinStorage = if let Some(storage_path) = config.storage_path.clone() {
-        SqliteStorage::open(
-            storage_path.join("dialogues.sqlite").to_str().unwrap(),
-            Json,
-        )
-        .await
-        .unwrap()
-        .erase()
-    } else {
-        InMemStorage::new().erase()
-    };
-
-    let countersign = Countersign::new();
+    let config = config::load().unwrap_or_else(|err| panic!("{err}"));
 
-    let pin_history = match config.max_pinned_channel_posts {
-        Some(max) => {
+    let rules = match (&config.rules_message, &config.rules_file) {
+        (Some(message_id), _) => Rules::Message(message_id.clone()),
+        (None, Some(file_name)) => {
             let storage_path = config
                 .storage_path
                 .as_deref()
-                .expect("MAX_PINNED_CHANNEL_POSTS requires STORAGE_PATH to be set");
-            PinHistory::load(storage_path, max).expect("failed to load pin history")
+                .expect("RULES_FILE requires STORAGE_PATH to be set");
+            Rules::load(storage_path, file_name).expect("failed to load rules")
         }
-        None => PinHistory::disabled(),
+        (None, None) => Rules::Disabled,
     };
 
+    let group_states = Arc::new(
+        GroupStates::open(&config, rules)
+            .await
+            .expect("failed to open group state"),
+    );
+
+    let banlist = Arc::new(match config.storage_path.as_deref() {
+        Some(storage_path) => Banlist::load(storage_path).expect("failed to load banlist"),
+        None => Banlist::in_memory(),
+    });
+
+    let scammer_list_sources = config.scammer_list_sources.clone().unwrap_or_default().0;
+    let countersign = Arc::new(match config.storage_path.as_deref() {
+        Some(storage_path) => Countersign::load(
+            storage_path,
+            scammer_list_sources,
+            banlist.clone(),
+            config.banlist_action,
+        )
+        .expect("failed to load scammer lists"),
+        None => Countersign::new(scammer_list_sources, banlist.clone(), config.banlist_action),
+    });
+
+    tokio::spawn({
+        let countersign = countersign.clone();
+        async move { countersign.run().await }
+    });
+
+    let audit_log = AuditLog::open(config.storage_path.as_deref())
+        .await
+        .expect("failed to open audit log");
+
+    let config = Arc::new(config);
+    let config_handle = Arc::new(ConfigHandle::new(config.clone()));
+    let audit_log = Arc::new(audit_log);
+    let locks = Arc::new(RequestLocks::default());
+
+    tokio::spawn(expiry::run(
+        bot.clone(),
+        group_states.clone(),
+        config_handle.clone(),
+        audit_log.clone(),
+        locks.clone(),
+    ));
+
+    let webhook_options = config.webhook_options();
+
+    if let Some(address) = config.metrics_listen_address {
+        let listener = tokio::net::TcpListener::bind(address)
+            .await
+            .expect("failed to bind METRICS_LISTEN_ADDRESS");
+        tokio::spawn(health::serve(
+            listener,
+            bot.clone(),
+            config.storage_path.clone(),
+        ));
+    }
+
+    #[cfg(unix)]
+    tokio::spawn(config::reload_on_hangup(config_handle.clone()));
+
     info!("bot started");
 
-    Dispatcher::builder(bot, schema())
+    let mut dispatcher = Dispatcher::builder(bot.clone(), schema())
         .dependencies(dptree::deps![
-            storage,
-            Arc::new(config),
-            Arc::new(countersign),
-            Arc::new(pin_history)
+            group_states,
+            config_handle,
+            countersign,
+            audit_log,
+            banlist,
+            locks
         ])
         .default_handler(|_| async move {
             // We ignore any update we don't know
         })
         .enable_ctrlc_handler()
-        .build()
-        .dispatch()
-        .await;
+        .build();
+
+    METRICS.set_dispatching(true);
+
+    match webhook_options {
+        Some(options) => {
+            let listener = listen_for_webhook(&bot, options)
+                .await
+                .expect("failed to set up webhook");
+
+            dispatcher
+                .dispatch_with_listener(
+                    listener,
+                    LoggingErrorHandler::with_custom_text("An error from the update listener"),
+                )
+                .await;
+
+            if let Err(err) = bot.delete_webhook().await {
+                error!("failed to delete webhook: {err}");
+            }
+        }
+        None => dispatcher.dispatch().await,
+    }
+
+    METRICS.set_dispatching(false);
+}
+
+/// The updates handled by `schema()`, which Telegram has to be asked for, as it only sends chat
+/// member updates on request.
+const ALLOWED_UPDATES: [AllowedUpdate; 5] = [
+    AllowedUpdate::Message,
+    AllowedUpdate::CallbackQuery,
+    AllowedUpdate::ChannelPost,
+    AllowedUpdate::ChatJoinRequest,
+    AllowedUpdate::ChatMember,
+];
+
+/// Registers the webhook and serves it. teloxide's own setup does not pass the allowed updates,
+/// so joins through invite links would never be tracked.
+async fn listen_for_webhook(
+    bot: &Bot,
+    mut options: webhooks::Options,
+) -> Result<impl UpdateListener<Err = Infallible>, Box<dyn Error + Send + Sync>> {
+    let secret_token = options.get_or_gen_secret_token().to_string();
+
+    bot.set_webhook(options.url.clone())
+        .allowed_updates(ALLOWED_UPDATES)
+        .secret_token(secret_token)
+        .await?;
+
+    let tcp_listener = tokio::net::TcpListener::bind(options.address).await?;
+    let (listener, stop_flag, router) = webhooks::axum_no_setup(options);
+
+    tokio::spawn(async move {
+        if let Err(err) = axum::serve(tcp_listener, router)
+            .with_graceful_shutdown(stop_flag)
+            .await
+        {
+            error!("webhook server failed: {err}");
+        }
+    });
+
+    Ok(listener)
+}
+
+/// Checks a secret token against Telegram's requirements, which teloxide would otherwise panic on.
+fn is_valid_secret_token(secret_token: &str) -> bool {
+    (1..=256).contains(&secret_token.len())
+        && secret_token
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
 }
 
 fn schema() -> UpdateHandler<Box<dyn Error + Send + Sync + 'static>> {
     use dptree::case;
 
     let command_handler = teloxide::filter_command::<Command, _>()
-        .branch(case![Command::Start].endpoint(start))
+        .branch(case![Command::Start(payload)].endpoint(start))
         .branch(case![Command::Help].endpoint(help))
         .branch(case![Command::Cancel].endpoint(cancel))
         .branch(case![Command::Privacy].endpoint(privacy));
 
+    let moderator_command_handler = dptree::filter(|msg: Message, config: Arc<Config>| {
+        msg.chat.id == ChatId(config.moderator_chat_id)
+    })
+    .chain(teloxide::filter_command::<ModeratorCommand, _>())
+    .branch(case![ModeratorCommand::Pending].endpoint(pending))
+    .branch(case![ModeratorCommand::Stats].endpoint(stats))
+    .branch(case![ModeratorCommand::History(target)].endpoint(history))
+    .branch(case![ModeratorCommand::Deny(reason)].endpoint(deny_with_reason))
+    .branch(case![ModeratorCommand::Report(target)].endpoint(report))
+    .branch(case![ModeratorCommand::Unreport(target)].endpoint(unreport))
+    .branch(case![ModeratorCommand::ExportBanlist].endpoint(export_banlist))
+    .branch(case![ModeratorCommand::ImportBanlist].endpoint(import_banlist))
+    .branch(case![ModeratorCommand::Reload].endpoint(reload))
+    .branch(
+        dptree::filter_map(|command: ModeratorCommand| match command {
+            ModeratorCommand::Approve(target) => Some((ReviewAction::Approve, target)),
+            ModeratorCommand::Block(target) => Some((ReviewAction::Block, target)),
+            ModeratorCommand::Unblock(target) => Some((ReviewAction::Unblock, target)),
+            _ => None,
+        })
+        .endpoint(moderate_user),
+    );
+
     let message_handler = Update::filter_message()
+        .branch(moderator_command_handler)
         .branch(case![State::Blocked].endpoint(blocked))
         .branch(command_handler)
         .branch(case![State::ReceiveReason].endpoint(receive_reason))
-        .branch(case![State::AwaitApproval { message_id }].endpoint(await_approval));
-
-    let callback_query_handler = Update::filter_callback_query().endpoint(review);
+        .branch(case![State::AcceptRules { join_request }].endpoint(await_rules_acceptance))
+        .branch(
+            case![State::ReceiveAnswer {
+                join_request,
+                rules_accepted_at,
+                answers
+            }]
+            .endpoint(receive_answer),
+        )
+        .branch(
+            case![State::AwaitApproval {
+                message_id,
+                join_request,
+                votes,
+                reminded_at
+            }]
+            .endpoint(await_approval),
+        );
+
+    let callback_query_handler = Update::filter_callback_query()
+        .branch(
+            dptree::filter(|query: CallbackQuery| query.data.as_deref() == Some(ACCEPT_RULES))
+                .branch(case![State::AcceptRules { join_request }].endpoint(accept_rules))
+                .endpoint(dismiss_callback_query),
+        )
+        .endpoint(review);
     let channel_post_handler = Update::filter_channel_post().endpoint(forward_channel_post);
 
-    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
-        .branch(message_handler)
-        .branch(callback_query_handler)
-        .branch(channel_post_handler)
+    // Join requests are keyed by the primary chat, so they have to be handled before entering
+    // the dialogue, which is keyed by the private chat with the applicant.
+    let chat_join_request_handler =
+        Update::filter_chat_join_request().endpoint(receive_join_request);
+    let chat_member_handler = Update::filter_chat_member().endpoint(track_join);
+
+    // Links to the bot for a group it does not serve are answered before selecting a group, as
+    // there is none to select.
+    let unknown_group_handler = Update::filter_message()
+        .filter(|msg: Message| msg.chat.is_private())
+        .filter_map(|msg: Message, me: Me| groups::start_payload(&msg, &me))
+        .endpoint(unknown_group);
+
+    // Handlers see the configuration as it was when the update arrived, even if it is reloaded
+    // in the meantime.
+    dptree::entry()
+        .map(|config: Arc<ConfigHandle>| config.current())
+        .branch(
+            dptree::filter_map_async(groups::select_group)
+                .map(|group: SelectedGroup| group.config)
+                .map(|group: SelectedGroup| group.state.storage)
+                .map(|group: SelectedGroup| group.state.pin_history)
+                .map(|group: SelectedGroup| group.state.rules)
+                .branch(chat_join_request_handler)
+                .branch(chat_member_handler)
+                .branch(
+                    dialogue::enter::<Update, ErasedStorage<State>, State, _>()
+                        .branch(message_handler)
+                        .branch(callback_query_handler)
+                        .branch(channel_post_handler),
+                ),
+        )
+        .branch(unknown_group_handler)
+}
+
+async fn unknown_group(bot: Bot, msg: Message) -> HandlerResult {
+    let loader = loader_from_message(&msg);
+    bot.send_message(msg.chat.id, fl!(loader, "group-unknown"))
+        .await?;
+    Ok(())
 }
 
 async fn forward_channel_post(
@@ -205,22 +640,24 @@ async fn forward_channel_post(
         .forward_message(primary_chat_id, channel_id, msg.id)
         .await?;
     bot.pin_chat_message(primary_chat_id, result.id).await?;
+    METRICS.record_pin();
 
     for evicted in pin_history.push(result.id).await {
-        if let Err(err) = bot
+        match bot
             .unpin_chat_message(primary_chat_id)
             .message_id(evicted)
             .await
         {
-            warn!("failed to unpin message {}: {err}", evicted.0);
+            Ok(_) => METRICS.record_unpin(),
+            Err(err) => warn!("failed to unpin message {}: {err}", evicted.0),
         }
     }
 
     Ok(())
 }
 
-fn locale_from_message(msg: &Message) -> LanguageIdentifier {
-    match msg.from.as_ref() {
+fn locale_from_user(user: Option<&User>) -> LanguageIdentifier {
+    match user {
         Some(user) => user.language_code.clone().unwrap_or("en".to_string()),
         None => "en".to_string(),
     }
@@ -228,11 +665,150 @@ fn locale_from_message(msg: &Message) -> LanguageIdentifier {
     .unwrap_or_else(|_| "en".to_string().parse().unwrap())
 }
 
+fn locale_from_message(msg: &Message) -> LanguageIdentifier {
+    locale_from_user(msg.from.as_ref())
+}
+
 fn loader_from_message(msg: &Message) -> FluentLanguageLoader {
     LANGUAGE_LOADER
         .select_languages_negotiate(&[locale_from_message(msg)], NegotiationStrategy::Filtering)
 }
 
+async fn decline_join_request(bot: &Bot, config: &Config, user_id: UserId) {
+    if let Err(err) = bot
+        .decline_chat_join_request(ChatId(config.primary_chat_id), user_id)
+        .await
+    {
+        warn!("failed to decline join request of {user_id}: {err}");
+    }
+}
+
+async fn receive_join_request(
+    bot: Bot,
+    request: ChatJoinRequest,
+    storage: JoinStorage,
+    config: Arc<Config>,
+    rules: Arc<Rules>,
+) -> HandlerResult {
+    if request.chat.id != ChatId(config.primary_chat_id) {
+        return Ok(());
+    }
+
+    let chat_id = request.user_chat_id;
+
+    match storage.clone().get_dialogue(chat_id).await? {
+        Some(State::Blocked) => {
+            decline_join_request(&bot, &config, request.from.id).await;
+            return Ok(());
+        }
+        Some(State::AwaitApproval {
+            message_id,
+            votes,
+            reminded_at,
+            ..
+        }) => {
+            // The user already applied through the bot, so approving that review should now
+            // approve the join request instead of issuing a separate invite link.
+            storage
+                .update_dialogue(
+                    chat_id,
+                    State::AwaitApproval {
+                        message_id,
+                        join_request: true,
+                        votes,
+                        reminded_at,
+                    },
+                )
+                .await?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let locale = locale_from_user(Some(&request.from));
+    let loader =
+        LANGUAGE_LOADER.select_languages_negotiate(&[&locale], NegotiationStrategy::Filtering);
+
+    let state = prompt_join_flow(&bot, chat_id, &loader, &config, &rules, true, None).await?;
+    storage.update_dialogue(chat_id, state).await?;
+
+    info!(user:debug = request.from; "Join request received");
+
+    Ok(())
+}
+
+async fn track_join(
+    bot: Bot,
+    update: ChatMemberUpdated,
+    config: Arc<Config>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    if update.chat.id != ChatId(config.primary_chat_id)
+        || update.old_chat_member.is_present()
+        || !update.new_chat_member.is_present()
+    {
+        return Ok(());
+    }
+
+    let Some(invite_link) = &update.invite_link else {
+        return Ok(());
+    };
+
+    let Some(record) = audit_log.invite_link(&invite_link.invite_link).await? else {
+        return Ok(());
+    };
+
+    audit_log
+        .mark_invite_link_joined(&record.invite_link, update.date)
+        .await?;
+
+    let user = &update.new_chat_member.user;
+    let joined_at = update.date.format("%Y-%m-%d %H:%M UTC").to_string();
+
+    let loader = config.moderator_loader();
+
+    // Links may be shared, so make it obvious when someone else joined in the applicant's place.
+    let note = if user.id == record.user_id {
+        fl!(loader, "moderator-joined", time = joined_at)
+    } else {
+        fl!(
+            loader,
+            "moderator-joined-by-other",
+            user = format!("{} [{}]", get_plaintext_display_name(user), user.id),
+            time = joined_at
+        )
+    };
+
+    annotate_review(&bot, &config, &audit_log, &record.request_id, &note).await?;
+
+    info!(user:debug = user; "Approved user joined");
+
+    Ok(())
+}
+
+/// Revokes the invite links which were issued but never used.
+async fn revoke_invite_links(
+    bot: &Bot,
+    audit_log: &AuditLog,
+    filter: UnusedInviteLinks,
+) -> HandlerResult {
+    for record in audit_log.unused_invite_links(filter).await? {
+        if let Err(err) = bot
+            .revoke_chat_invite_link(record.chat_id, &record.invite_link)
+            .await
+        {
+            warn!("failed to revoke invite link {}: {err}", record.invite_link);
+        }
+
+        // Failed revocations are not retried, as the link expires on its own anyway.
+        audit_log
+            .mark_invite_link_revoked(&record.invite_link, Utc::now())
+            .await?;
+    }
+
+    Ok(())
+}
+
 async fn blocked(bot: Bot, _dialogue: JoinDialogue, msg: Message) -> HandlerResult {
     if !msg.chat.is_private() {
         return Ok(());
@@ -248,6 +824,7 @@ async fn start(
     bot: Bot,
     dialogue: JoinDialogue,
     config: Arc<Config>,
+    rules: Arc<Rules>,
     msg: Message,
 ) -> HandlerResult {
     if !msg.chat.is_private() {
@@ -275,9 +852,112 @@ async fn start(
         return Ok(());
     }
 
-    bot.send_message(msg.chat.id, fl!(loader, "reason-prompt"))
+    let state = prompt_join_flow(&bot, msg.chat.id, &loader, &config, &rules, false, None).await?;
+    dialogue.update(state).await?;
+    Ok(())
+}
+
+const ACCEPT_RULES: &str = "accept-rules";
+
+/// Sends the next prompt of the join flow and returns the state awaiting its answer.
+async fn prompt_join_flow(
+    bot: &Bot,
+    chat_id: ChatId,
+    loader: &FluentLanguageLoader,
+    config: &Config,
+    rules: &Rules,
+    join_request: bool,
+    rules_accepted_at: Option<DateTime<Utc>>,
+) -> Result<State, RequestError> {
+    if rules_accepted_at.is_none()
+        && let Some(text) = rules.text(loader)
+    {
+        let intro = if join_request {
+            fl!(loader, "join-request-rules-intro")
+        } else {
+            fl!(loader, "rules-intro")
+        };
+
+        bot.send_message(chat_id, format!("{intro}\n\n{text}"))
+            .reply_markup(InlineKeyboardMarkup::new(vec![vec![
+                InlineKeyboardButton::callback(fl!(loader, "rules-agree"), ACCEPT_RULES),
+            ]]))
+            .await?;
+
+        return Ok(State::AcceptRules { join_request });
+    }
+
+    let prompt = match config.join_questions().first() {
+        // The rules already greeted users coming from a join request.
+        None if join_request && !rules.is_enabled() => fl!(loader, "join-request-prompt"),
+        None => fl!(loader, "reason-prompt"),
+        Some(question) if join_request && !rules.is_enabled() => format!(
+            "{}\n\n{}",
+            fl!(loader, "join-request-questions-intro"),
+            loader.get(question)
+        ),
+        Some(question) => format!(
+            "{}\n\n{}",
+            fl!(loader, "questions-intro"),
+            loader.get(question)
+        ),
+    };
+
+    bot.send_message(chat_id, prompt).await?;
+
+    Ok(State::ReceiveAnswer {
+        join_request,
+        rules_accepted_at,
+        answers: Vec::new(),
+    })
+}
+
+async fn await_rules_acceptance(bot: Bot, msg: Message) -> HandlerResult {
+    let loader = loader_from_message(&msg);
+    bot.send_message(msg.chat.id, fl!(loader, "rules-pending"))
         .await?;
-    dialogue.update(State::ReceiveReason).await?;
+    Ok(())
+}
+
+/// Answers callback queries which are no longer relevant, e.g. after the rules were accepted.
+async fn dismiss_callback_query(bot: Bot, query: CallbackQuery) -> HandlerResult {
+    bot.answer_callback_query(query.id).await?;
+    Ok(())
+}
+
+async fn accept_rules(
+    bot: Bot,
+    dialogue: JoinDialogue,
+    query: CallbackQuery,
+    join_request: bool,
+    config: Arc<Config>,
+    rules: Arc<Rules>,
+) -> HandlerResult {
+    bot.answer_callback_query(query.id.clone()).await?;
+
+    if let Some(message) = query.regular_message() {
+        bot.edit_message_reply_markup(message.chat.id, message.id)
+            .await?;
+    }
+
+    let locale = locale_from_user(Some(&query.from));
+    let loader =
+        LANGUAGE_LOADER.select_languages_negotiate(&[&locale], NegotiationStrategy::Filtering);
+
+    let state = prompt_join_flow(
+        &bot,
+        dialogue.chat_id(),
+        &loader,
+        &config,
+        &rules,
+        join_request,
+        Some(Utc::now()),
+    )
+    .await?;
+    dialogue.update(state).await?;
+
+    info!(user:debug = query.from; "Rules accepted");
+
     Ok(())
 }
 
@@ -301,9 +981,30 @@ async fn cancel(
         return Ok(());
     }
 
-    if let Some(State::AwaitApproval { message_id }) = dialogue.get().await? {
-        bot.delete_message(ChatId(config.moderator_chat_id), message_id)
-            .await?;
+    match dialogue.get().await? {
+        Some(State::AwaitApproval {
+            message_id,
+            join_request,
+            ..
+        }) => {
+            bot.delete_message(ChatId(config.moderator_chat_id), message_id)
+                .await?;
+
+            if join_request && let Some(from) = msg.from.as_ref() {
+                decline_join_request(&bot, &config, from.id).await;
+            }
+        }
+        Some(
+            State::AcceptRules { join_request: true }
+            | State::ReceiveAnswer {
+                join_request: true, ..
+            },
+        ) => {
+            if let Some(from) = msg.from.as_ref() {
+                decline_join_request(&bot, &config, from.id).await;
+            }
+        }
+        _ => {}
     }
 
     let loader = loader_from_message(&msg);
@@ -320,28 +1021,574 @@ async fn privacy(bot: Bot, msg: Message) -> HandlerResult {
     Ok(())
 }
 
-async fn await_approval(bot: Bot, msg: Message) -> HandlerResult {
-    let loader = loader_from_message(&msg);
-    bot.send_message(msg.chat.id, fl!(loader, "under-review"))
-        .await?;
-    Ok(())
-}
-
-fn get_markdown_display_name(user: &User) -> String {
-    let mut full_name = user.first_name.clone();
+async fn history(
+    bot: Bot,
+    msg: Message,
+    target: String,
+    config: Arc<Config>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    let target = target.trim();
+    let loader = config.moderator_loader();
 
-    if let Some(last_name) = user.last_name.clone() {
-        full_name.push(' ');
-        full_name.push_str(&last_name);
-    }
+    let user_ids = if let Some(username) = target.strip_prefix('@') {
+        audit_log.find_user_ids(username).await?
+    } else if let Ok(user_id) = target.parse::<u64>() {
+        vec![UserId(user_id)]
+    } else {
+        bot.send_message(msg.chat.id, fl!(loader, "moderator-history-usage"))
+            .await?;
+        return Ok(());
+    };
 
-    let mut display_name = format!("[{}](tg://user?id={})", escape(&full_name), user.id);
+    let mut entries = Vec::new();
 
-    if let Some(username) = user.username.clone() {
-        display_name.push_str(&escape(&format!(" (@{})", &username)));
+    for user_id in user_ids {
+        entries.extend(audit_log.history(user_id, HISTORY_LIMIT).await?);
     }
 
-    display_name
+    if entries.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            fl!(loader, "moderator-history-empty", target = target),
+        )
+        .await?;
+        return Ok(());
+    }
+
+    entries.sort_by_key(|entry| Reverse(entry.request.submitted_at));
+    entries.truncate(HISTORY_LIMIT as usize);
+
+    let text = entries
+        .iter()
+        .map(|entry| format_history_entry(&loader, entry))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    bot.send_message(
+        msg.chat.id,
+        format!(
+            "{}\n\n{text}",
+            fl!(loader, "moderator-history", target = target)
+        ),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Returns the requests which are still awaiting a decision along with their votes, either for
+/// a single group or for all of them.
+async fn pending_requests(
+    group_states: &GroupStates,
+    audit_log: &AuditLog,
+    group: Option<ChatId>,
+) -> Result<Vec<(RequestRecord, Vec<Vote>)>, Box<dyn Error + Send + Sync>> {
+    let mut pending = Vec::new();
+
+    for request in audit_log.undecided_requests().await? {
+        if group.is_some_and(|group| group != request.chat_id) {
+            continue;
+        }
+
+        let Some(state) = group_states.get(request.chat_id) else {
+            continue;
+        };
+
+        let chat_id = ChatId(request.user_id.0 as i64);
+
+        if let Some(State::AwaitApproval {
+            message_id, votes, ..
+        }) = state.storage.clone().get_dialogue(chat_id).await?
+            && Some(message_id) == request.message_id
+        {
+            pending.push((request, votes));
+        }
+    }
+
+    Ok(pending)
+}
+
+async fn pending(
+    bot: Bot,
+    msg: Message,
+    config: Arc<Config>,
+    group_states: Arc<GroupStates>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    let pending = pending_requests(
+        &group_states,
+        &audit_log,
+        Some(ChatId(config.primary_chat_id)),
+    )
+    .await?;
+    let loader = config.moderator_loader();
+
+    if pending.is_empty() {
+        bot.send_message(msg.chat.id, fl!(loader, "moderator-pending-empty"))
+            .await?;
+        return Ok(());
+    }
+
+    let text = pending
+        .iter()
+        .map(|(request, votes)| {
+            fl!(
+                loader,
+                "moderator-pending-entry",
+                time = request
+                    .submitted_at
+                    .format("%Y-%m-%d %H:%M UTC")
+                    .to_string(),
+                user = format!("{} [{}]", request.display_name, request.user_id),
+                votes = votes.len()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    bot.send_message(
+        msg.chat.id,
+        format!("{}\n\n{text}", fl!(loader, "moderator-pending")),
+    )
+    .await?;
+    Ok(())
+}
+
+async fn stats(
+    bot: Bot,
+    msg: Message,
+    config: Arc<Config>,
+    group_states: Arc<GroupStates>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    let stats = audit_log.stats().await?;
+    let pending = pending_requests(&group_states, &audit_log, None).await?;
+    let loader = config.moderator_loader();
+
+    let mut text = fl!(
+        loader,
+        "moderator-stats",
+        requests = stats.requests,
+        open = pending.len()
+    );
+
+    for (outcome, count) in stats.decisions {
+        text.push_str(&format!("\n{}: {count}", outcome_label(&loader, outcome)));
+    }
+
+    bot.send_message(msg.chat.id, text).await?;
+    Ok(())
+}
+
+async fn moderate_user(
+    bot: Bot,
+    msg: Message,
+    (action, target): (ReviewAction, String),
+    storage: JoinStorage,
+    config: Arc<Config>,
+    audit_log: Arc<AuditLog>,
+    locks: Arc<RequestLocks>,
+) -> HandlerResult {
+    let Some(reviewer) = msg.from.clone() else {
+        return Ok(());
+    };
+
+    let Ok(user_id) = target.trim().parse::<u64>() else {
+        bot.send_message(
+            msg.chat.id,
+            fl!(config.moderator_loader(), "moderator-user-id-missing"),
+        )
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+        return Ok(());
+    };
+
+    let loader = config.moderator_loader();
+    let request_id = audit_log
+        .latest_request_id(ChatId(config.primary_chat_id), UserId(user_id))
+        .await?;
+    let _guard = match &request_id {
+        Some(request_id) => Some(locks.lock(request_id).await),
+        None => None,
+    };
+    let request = match &request_id {
+        Some(request_id) => audit_log.request(request_id).await?,
+        None => None,
+    };
+
+    let Some(request) = request else {
+        bot.send_message(
+            msg.chat.id,
+            fl!(loader, "moderator-request-missing", user_id = user_id),
+        )
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+        return Ok(());
+    };
+
+    // Commands are subject to the same check as the buttons, so that a request decided through
+    // either of them is not decided again.
+    let mut refusal = prior_decision(&config, &audit_log, &request, action).await?;
+
+    if refusal.is_none() {
+        let dialogue_state = storage.clone().get_dialogue(ChatId(user_id as i64)).await?;
+
+        // Decided requests only get here for the follow-up actions of their decision.
+        refusal = match (action, dialogue_state) {
+            (ReviewAction::Unblock, Some(State::Blocked)) => None,
+            (ReviewAction::Unblock, _) => {
+                Some(fl!(loader, "moderator-user-not-blocked", user_id = user_id))
+            }
+            (_, Some(State::Blocked)) => {
+                Some(fl!(loader, "moderator-user-blocked", user_id = user_id))
+            }
+            (_, Some(State::AwaitApproval { .. })) => None,
+            _ if request.status == RequestStatus::Pending => {
+                Some(fl!(loader, "moderator-request-missing", user_id = user_id))
+            }
+            _ => None,
+        };
+    }
+
+    if let Some(text) = refusal {
+        bot.send_message(msg.chat.id, text)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    }
+
+    let review = review_from_request(action, &request);
+
+    info!(review:debug; "Received review command");
+
+    decide(
+        &bot,
+        &storage,
+        &config,
+        &audit_log,
+        review,
+        &reviewer,
+        ReviewOrigin::Command(msg.id),
+    )
+    .await
+}
+
+async fn deny_with_reason(
+    bot: Bot,
+    msg: Message,
+    reason: String,
+    storage: JoinStorage,
+    config: Arc<Config>,
+    audit_log: Arc<AuditLog>,
+    locks: Arc<RequestLocks>,
+) -> HandlerResult {
+    let Some(reviewer) = msg.from.clone() else {
+        return Ok(());
+    };
+
+    let reason = reason.trim();
+    let review_message = msg.reply_to_message();
+
+    // The request is identified through the callback data of the review message's buttons.
+    let request_id = review_message
+        .and_then(|message| message.reply_markup())
+        .and_then(|markup| {
+            markup
+                .inline_keyboard
+                .iter()
+                .flatten()
+                .find_map(|button| match &button.kind {
+                    InlineKeyboardButtonKind::CallbackData(data) => {
+                        ReviewCallback::try_from(data.clone()).ok()
+                    }
+                    _ => None,
+                })
+        })
+        .map(|callback| callback.request_id);
+
+    let _guard = match &request_id {
+        Some(request_id) => Some(locks.lock(request_id).await),
+        None => None,
+    };
+    let request = match &request_id {
+        Some(request_id) => audit_log.request(request_id).await?,
+        None => None,
+    };
+
+    let (Some(review_message), Some(request)) = (review_message, request) else {
+        bot.send_message(
+            msg.chat.id,
+            fl!(config.moderator_loader(), "moderator-deny-usage"),
+        )
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+        return Ok(());
+    };
+
+    if reason.is_empty() {
+        bot.send_message(
+            msg.chat.id,
+            fl!(config.moderator_loader(), "moderator-deny-reason-missing"),
+        )
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+        return Ok(());
+    }
+
+    if let Some(text) = prior_decision(&config, &audit_log, &request, ReviewAction::Deny).await? {
+        bot.send_message(msg.chat.id, text)
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    }
+
+    let review = review_from_request(ReviewAction::Deny, &request)
+        .with_reason(DenyReason::Custom(reason.to_string()));
+
+    let is_pending = matches!(
+        storage.clone().get_dialogue(review.chat_id).await?,
+        Some(State::AwaitApproval { message_id, .. }) if message_id == review_message.id
+    );
+
+    if !is_pending {
+        bot.send_message(
+            msg.chat.id,
+            fl!(config.moderator_loader(), "moderator-review-not-pending"),
+        )
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+        return Ok(());
+    }
+
+    info!(review:debug; "Received review command");
+
+    decide(
+        &bot,
+        &storage,
+        &config,
+        &audit_log,
+        review,
+        &reviewer,
+        ReviewOrigin::Command(msg.id),
+    )
+    .await
+}
+
+async fn report(
+    bot: Bot,
+    msg: Message,
+    target: String,
+    config: Arc<Config>,
+    banlist: Arc<Banlist>,
+) -> HandlerResult {
+    let loader = config.moderator_loader();
+    let (user_id, reason) = match target.trim().split_once(char::is_whitespace) {
+        Some((user_id, reason)) => (user_id, Some(reason.trim().to_string())),
+        None => (target.trim(), None),
+    };
+
+    let Ok(user_id) = user_id.parse::<u64>() else {
+        bot.send_message(msg.chat.id, fl!(loader, "moderator-report-usage"))
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    };
+
+    let result = banlist
+        .add(BanlistEntry {
+            user_id: UserId(user_id),
+            reason,
+            added_by: msg.from.as_ref().map(get_plaintext_display_name),
+            added_at: Utc::now(),
+        })
+        .await;
+
+    let text = match result {
+        Ok(true) => fl!(loader, "moderator-banlist-added", user_id = user_id),
+        Ok(false) => fl!(loader, "moderator-banlist-updated", user_id = user_id),
+        Err(err) => fl!(loader, "moderator-banlist-failed", error = err.to_string()),
+    };
+
+    bot.send_message(msg.chat.id, text)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+    Ok(())
+}
+
+async fn unreport(
+    bot: Bot,
+    msg: Message,
+    target: String,
+    config: Arc<Config>,
+    banlist: Arc<Banlist>,
+) -> HandlerResult {
+    let loader = config.moderator_loader();
+
+    let Ok(user_id) = target.trim().parse::<u64>() else {
+        bot.send_message(msg.chat.id, fl!(loader, "moderator-user-id-missing"))
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    };
+
+    let text = match banlist.remove(UserId(user_id)).await {
+        Ok(true) => fl!(loader, "moderator-banlist-removed", user_id = user_id),
+        Ok(false) => fl!(loader, "moderator-banlist-missing", user_id = user_id),
+        Err(err) => fl!(loader, "moderator-banlist-failed", error = err.to_string()),
+    };
+
+    bot.send_message(msg.chat.id, text)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+    Ok(())
+}
+
+async fn export_banlist(bot: Bot, msg: Message, banlist: Arc<Banlist>) -> HandlerResult {
+    let bytes = banlist.export().await?;
+
+    bot.send_document(
+        msg.chat.id,
+        InputFile::memory(bytes).file_name("banlist.json"),
+    )
+    .reply_parameters(ReplyParameters::new(msg.id))
+    .await?;
+    Ok(())
+}
+
+async fn import_banlist(
+    bot: Bot,
+    msg: Message,
+    config: Arc<Config>,
+    banlist: Arc<Banlist>,
+) -> HandlerResult {
+    let loader = config.moderator_loader();
+
+    let Some(document) = msg
+        .reply_to_message()
+        .and_then(|message| message.document())
+    else {
+        bot.send_message(msg.chat.id, fl!(loader, "moderator-import-usage"))
+            .reply_parameters(ReplyParameters::new(msg.id))
+            .await?;
+        return Ok(());
+    };
+
+    let file = bot.get_file(document.file.id.clone()).await?;
+    let mut bytes = Vec::new();
+    bot.download_file(&file.path, &mut bytes).await?;
+
+    let text = match banlist.import(&bytes).await {
+        Ok(added) => fl!(loader, "moderator-import-done", count = added),
+        Err(ImportError::Invalid(err)) => {
+            fl!(loader, "moderator-import-invalid", error = err.to_string())
+        }
+        Err(ImportError::Persist(err)) => {
+            fl!(loader, "moderator-banlist-failed", error = err.to_string())
+        }
+    };
+
+    bot.send_message(msg.chat.id, text)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+    Ok(())
+}
+
+const HISTORY_LIMIT: u32 = 10;
+const HISTORY_REASON_LENGTH: usize = 200;
+
+async fn reload(bot: Bot, msg: Message, config: Arc<ConfigHandle>) -> HandlerResult {
+    let result = config.reload();
+    // A reloaded configuration may already answer in a different language.
+    let loader = config.current().moderator_loader();
+
+    let text = match result {
+        Ok(ignored) if ignored.is_empty() => fl!(loader, "moderator-reloaded"),
+        Ok(ignored) => fl!(
+            loader,
+            "moderator-reloaded-restart",
+            settings = ignored.join(", ")
+        ),
+        Err(err) => fl!(loader, "moderator-reload-failed", error = err.to_string()),
+    };
+
+    bot.send_message(msg.chat.id, text)
+        .reply_parameters(ReplyParameters::new(msg.id))
+        .await?;
+    Ok(())
+}
+
+fn format_history_entry(loader: &FluentLanguageLoader, entry: &HistoryEntry) -> String {
+    let mut reason: String = entry
+        .request
+        .reason
+        .chars()
+        .take(HISTORY_REASON_LENGTH)
+        .collect();
+
+    if reason.len() < entry.request.reason.len() {
+        reason.push('…');
+    }
+
+    let mut text = format!(
+        "{} – {} [{}]\n{}",
+        entry.request.submitted_at.format("%Y-%m-%d %H:%M UTC"),
+        entry.request.display_name,
+        entry.request.user_id,
+        reason,
+    );
+
+    if entry.decisions.is_empty() {
+        text.push_str(&format!("\n→ {}", fl!(loader, "moderator-history-pending")));
+    }
+
+    for decision in &entry.decisions {
+        let time = decision.decided_at.format("%Y-%m-%d %H:%M UTC").to_string();
+
+        let line = match decision.outcome {
+            Outcome::Expired => fl!(
+                loader,
+                "moderator-history-expired",
+                time = time,
+                result = decision.send_result.as_str()
+            ),
+            outcome => fl!(
+                loader,
+                "moderator-history-decision",
+                action = outcome_label(loader, outcome),
+                reviewers = decision.reviewer.as_str(),
+                time = time,
+                result = decision.send_result.as_str()
+            ),
+        };
+
+        text.push_str(&format!("\n→ {line}"));
+    }
+
+    text
+}
+
+async fn await_approval(bot: Bot, msg: Message) -> HandlerResult {
+    let loader = loader_from_message(&msg);
+    bot.send_message(msg.chat.id, fl!(loader, "under-review"))
+        .await?;
+    Ok(())
+}
+
+fn get_markdown_display_name(user: &User) -> String {
+    let mut full_name = user.first_name.clone();
+
+    if let Some(last_name) = user.last_name.clone() {
+        full_name.push(' ');
+        full_name.push_str(&last_name);
+    }
+
+    let mut display_name = format!("[{}](tg://user?id={})", escape(&full_name), user.id);
+
+    if let Some(username) = user.username.clone() {
+        display_name.push_str(&escape(&format!(" (@{})", &username)));
+    }
+
+    display_name
 }
 
 fn get_plaintext_display_name(user: &User) -> String {
@@ -365,191 +1612,833 @@ async fn receive_reason(
     msg: Message,
     config: Arc<Config>,
     countersign: Arc<Countersign>,
+    audit_log: Arc<AuditLog>,
 ) -> HandlerResult {
-    let locale = locale_from_message(&msg);
-    let loader =
-        LANGUAGE_LOADER.select_languages_negotiate(&[&locale], NegotiationStrategy::Filtering);
+    receive_answer(
+        bot,
+        dialogue,
+        msg,
+        (false, None, Vec::new()),
+        config,
+        countersign,
+        audit_log,
+    )
+    .await
+}
 
-    let reason = match msg.text() {
-        Some(text) => text.to_owned(),
+async fn receive_answer(
+    bot: Bot,
+    dialogue: JoinDialogue,
+    msg: Message,
+    (join_request, rules_accepted_at, mut answers): (bool, Option<DateTime<Utc>>, Vec<String>),
+    config: Arc<Config>,
+    countersign: Arc<Countersign>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    let questions = config.join_questions();
+
+    let answer = match msg.text() {
+        Some(text) => text.trim().to_owned(),
         None => {
-            bot.send_message(msg.chat.id, fl!(loader, "reason-missing"))
-                .await?;
+            let loader = loader_from_message(&msg);
+            let text = if questions.is_empty() {
+                fl!(loader, "reason-missing")
+            } else {
+                fl!(loader, "answer-missing")
+            };
+            bot.send_message(msg.chat.id, text).await?;
             return Ok(());
         }
     };
 
+    answers.push(answer);
+
+    if let Some(question) = questions.get(answers.len()) {
+        let loader = loader_from_message(&msg);
+        bot.send_message(msg.chat.id, loader.get(question)).await?;
+        dialogue
+            .update(State::ReceiveAnswer {
+                join_request,
+                rules_accepted_at,
+                answers,
+            })
+            .await?;
+        return Ok(());
+    }
+
+    submit_for_review(
+        bot,
+        dialogue,
+        msg,
+        (join_request, rules_accepted_at, answers),
+        config,
+        countersign,
+        audit_log,
+    )
+    .await
+}
+
+/// Posts the collected answers to the moderator chat for review.
+async fn submit_for_review(
+    bot: Bot,
+    dialogue: JoinDialogue,
+    msg: Message,
+    (join_request, rules_accepted_at, answers): (bool, Option<DateTime<Utc>>, Vec<String>),
+    config: Arc<Config>,
+    countersign: Arc<Countersign>,
+    audit_log: Arc<AuditLog>,
+) -> HandlerResult {
+    let questions = config.join_questions();
+    let moderator_loader = config.moderator_loader();
+
+    let (mut details, reason) = if questions.is_empty() {
+        (
+            format!(
+                "{}\n\n{}",
+                escape(&fl!(moderator_loader, "moderator-review-reason")),
+                escape(&answers[0])
+            ),
+            answers[0].clone(),
+        )
+    } else {
+        let pairs = questions
+            .iter()
+            .zip(answers.iter())
+            .map(|(question, answer)| (moderator_loader.get(question), answer));
+        let details = pairs
+            .clone()
+            .map(|(question, answer)| format!("*{}*\n{}", escape(&question), escape(answer)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let reason = pairs
+            .map(|(question, answer)| format!("{question}\n{answer}"))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        (
+            format!(
+                "{}\n\n{details}",
+                escape(&fl!(moderator_loader, "moderator-review-answers"))
+            ),
+            reason,
+        )
+    };
+
+    if let Some(rules_accepted_at) = rules_accepted_at {
+        details.push_str(&format!(
+            "\n\n_{}_",
+            escape(&fl!(
+                moderator_loader,
+                "moderator-rules-accepted",
+                time = rules_accepted_at.format("%Y-%m-%d %H:%M UTC").to_string()
+            ))
+        ));
+    }
+
+    let locale = locale_from_message(&msg);
+    let loader =
+        LANGUAGE_LOADER.select_languages_negotiate(&[&locale], NegotiationStrategy::Filtering);
+
     let user = match msg.from.as_ref() {
         Some(user) => user,
         None => return Ok(()),
     };
 
-    let is_banned = match bot
-        .get_chat_member(ChatId(config.primary_chat_id), user.id)
+    let is_banned = is_banned_from_primary_chat(&bot, &config, user.id).await?;
+
+    let matches = countersign.matching_sources(user.id).await;
+
+    METRICS.record_request();
+    if apply_scammer_action(
+        &bot,
+        &dialogue,
+        &msg,
+        &config,
+        &audit_log,
+        &matches,
+        (join_request, &reason),
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    let scammer_tags: String = matches
+        .iter()
+        .map(|source| {
+            format!(
+                " *\\[__{}__]\\)*",
+                escape(
+                    &fl!(
+                        moderator_loader,
+                        "moderator-tag-listed",
+                        source = source.name.as_str()
+                    )
+                    .to_uppercase()
+                )
+            )
+        })
+        .collect();
+
+    let request_id = new_request_id();
+
+    // The request is stored before the review is posted, as its buttons refer to it.
+    let record = RequestRecord {
+        request_id: request_id.clone(),
+        chat_id: ChatId(config.primary_chat_id),
+        user_id: user.id,
+        display_name: get_plaintext_display_name(user),
+        username: user.username.clone(),
+        reason,
+        locale: locale.to_string(),
+        join_request,
+        status: RequestStatus::Pending,
+        message_id: None,
+        submitted_at: Utc::now(),
+    };
+    audit_log.record_request(&record).await?;
+
+    let moderator_message = match bot
+        .send_message(
+            ChatId(config.moderator_chat_id),
+            format!(
+                "{}{}{} {}",
+                get_markdown_display_name(user),
+                if is_banned {
+                    format!(
+                        " *\\[__{}__]\\)*",
+                        escape(&fl!(moderator_loader, "moderator-tag-banned"))
+                    )
+                } else {
+                    String::new()
+                },
+                scammer_tags,
+                details,
+            ),
+        )
+        .parse_mode(ParseMode::MarkdownV2)
+        .reply_markup(review_keyboard(&config, &request_id, is_banned))
         .await
     {
-        Ok(chat_member) => chat_member.is_banned(),
-        Err(RequestError::Api(ApiError::UserNotFound)) => false,
-        Err(error) => return Err(error.into()),
+        Ok(moderator_message) => moderator_message,
+        Err(err) => {
+            if let Err(err) = audit_log.discard_request(&request_id).await {
+                error!("failed to discard request {request_id}: {err}");
+            }
+
+            return Err(err.into());
+        }
     };
 
-    let is_known_scammer = countersign.is_known_scammer(user.id).await;
+    audit_log
+        .set_request_message(&request_id, moderator_message.id)
+        .await?;
 
-    let keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
+    bot.send_message(msg.chat.id, fl!(loader, "reason-received"))
+        .await?;
+    dialogue
+        .update(State::AwaitApproval {
+            message_id: moderator_message.id,
+            join_request,
+            votes: Vec::new(),
+            reminded_at: None,
+        })
+        .await?;
+
+    let review_message = ReviewMessage {
+        request_id,
+        text: moderator_message.text().unwrap_or_default().to_string(),
+        entities: moderator_message.entities().unwrap_or_default().to_vec(),
+    };
+
+    if let Err(err) = audit_log.record_review_message(&review_message).await {
+        error!("failed to record review message: {err}");
+    }
+
+    info!(user:debug; "Join reason received");
+
+    Ok(())
+}
+
+async fn is_banned_from_primary_chat(
+    bot: &Bot,
+    config: &Config,
+    user_id: UserId,
+) -> Result<bool, RequestError> {
+    match bot
+        .get_chat_member(ChatId(config.primary_chat_id), user_id)
+        .await
+    {
+        Ok(chat_member) => Ok(chat_member.is_banned()),
+        Err(RequestError::Api(ApiError::UserNotFound)) => Ok(false),
+        Err(error) => Err(error),
+    }
+}
+
+/// Builds the buttons of a pending review.
+fn review_keyboard(config: &Config, request_id: &str, is_banned: bool) -> InlineKeyboardMarkup {
+    let loader = config.moderator_loader();
+    let callback = |action| ReviewCallback::new(action, request_id);
+
+    let mut keyboard: Vec<Vec<InlineKeyboardButton>> = vec![
         vec![
             if is_banned {
                 InlineKeyboardButton::callback(
-                    "Unban & Approve",
-                    Review::new(
-                        ReviewAction::UnbanAndApprove,
-                        msg.chat.id,
-                        user.id,
-                        locale.clone(),
-                    ),
+                    fl!(loader, "moderator-button-unban-approve"),
+                    callback(ReviewAction::UnbanAndApprove),
                 )
             } else {
                 InlineKeyboardButton::callback(
-                    "Approve",
-                    Review::new(ReviewAction::Approve, msg.chat.id, user.id, locale.clone()),
+                    fl!(loader, "moderator-button-approve"),
+                    callback(ReviewAction::Approve),
                 )
             },
             InlineKeyboardButton::callback(
-                "Deny",
-                Review::new(ReviewAction::Deny, msg.chat.id, user.id, locale.clone()),
+                fl!(loader, "moderator-button-deny"),
+                callback(ReviewAction::Deny),
             ),
         ],
         vec![
             InlineKeyboardButton::callback(
-                "Block",
-                Review::new(ReviewAction::Block, msg.chat.id, user.id, locale.clone()),
+                fl!(loader, "moderator-button-block"),
+                callback(ReviewAction::Block),
             ),
             InlineKeyboardButton::callback(
-                "Request contact",
-                Review::new(ReviewAction::RequestContact, msg.chat.id, user.id, locale),
+                fl!(loader, "moderator-button-request-contact"),
+                callback(ReviewAction::RequestContact),
             ),
         ],
+        vec![InlineKeyboardButton::callback(
+            fl!(loader, "moderator-button-block-report"),
+            callback(ReviewAction::BlockAndReport),
+        )],
     ];
-    let keyboard_markup = InlineKeyboardMarkup::new(keyboard);
 
-    let moderator_message = bot
-        .send_message(
-            ChatId(config.moderator_chat_id),
-            format!(
-                "{}{} would like to join for the following reason:\n\n{}{}",
-                get_markdown_display_name(user),
-                if is_banned {
-                    " *\\[__BANNED__]\\)*"
-                } else {
-                    ""
-                },
-                if is_known_scammer {
-                    " *\\[__KNOWN SCAMMER__]\\)*"
-                } else {
-                    ""
-                },
-                escape(reason.trim()),
+    for (index, reason) in config.deny_reasons().iter().enumerate() {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            fl!(
+                loader,
+                "moderator-button-deny-reason",
+                reason = loader.get(reason)
             ),
-        )
-        .parse_mode(ParseMode::MarkdownV2)
-        .reply_markup(keyboard_markup)
+            callback(ReviewAction::Deny).with_reason(index as u8),
+        )]);
+    }
+    InlineKeyboardMarkup::new(keyboard)
+}
+
+/// Builds the buttons left on a review message after a decision, optionally offering to undo it.
+fn follow_up_keyboard(
+    loader: &FluentLanguageLoader,
+    action: ReviewAction,
+    request_id: &str,
+    undo: bool,
+) -> Option<InlineKeyboardMarkup> {
+    let mut keyboard = Vec::new();
+
+    match action {
+        ReviewAction::Block | ReviewAction::BlockAndReport => {
+            keyboard.push(vec![InlineKeyboardButton::callback(
+                fl!(loader, "moderator-button-unblock"),
+                ReviewCallback::new(ReviewAction::Unblock, request_id),
+            )]);
+        }
+        ReviewAction::RequestContact => {
+            keyboard.push(vec![InlineKeyboardButton::callback(
+                fl!(loader, "moderator-button-block"),
+                ReviewCallback::new(ReviewAction::Block, request_id),
+            )]);
+        }
+        _ => {}
+    }
+
+    if undo {
+        keyboard.push(vec![InlineKeyboardButton::callback(
+            fl!(loader, "moderator-button-undo"),
+            ReviewCallback::new(ReviewAction::Undo, request_id),
+        )]);
+    }
+
+    (!keyboard.is_empty()).then(|| InlineKeyboardMarkup::new(keyboard))
+}
+
+/// Returns whether a decision made through the review buttons may be undone.
+fn is_undoable(action: ReviewAction) -> bool {
+    !matches!(action, ReviewAction::Unblock | ReviewAction::Undo)
+}
+
+/// Takes the strictest action configured for the scammer lists an applicant appears on instead
+/// of posting a review, and leaves a summary in the moderator chat. Returns whether an action was
+/// taken, which is not the case when the lists only label the review.
+async fn apply_scammer_action(
+    bot: &Bot,
+    dialogue: &JoinDialogue,
+    msg: &Message,
+    config: &Config,
+    audit_log: &AuditLog,
+    matches: &[SourceMatch],
+    (join_request, reason): (bool, &str),
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let Some(user) = msg.from.as_ref() else {
+        return Ok(false);
+    };
+
+    let action = matches
+        .iter()
+        .map(|source| source.action)
+        .max()
+        .unwrap_or_default();
+    let sources = matches
+        .iter()
+        .map(|source| source.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let moderator_loader = config.moderator_loader();
+    let applicant = format!("{} [{}]", get_plaintext_display_name(user), user.id);
+
+    let (review_action, mut summary) = match action {
+        ScammerAction::Label => return Ok(false),
+        ScammerAction::Deny => (
+            ReviewAction::Deny,
+            fl!(
+                moderator_loader,
+                "moderator-automatic-deny",
+                user = applicant,
+                sources = sources
+            ),
+        ),
+        ScammerAction::Block => (
+            ReviewAction::Block,
+            fl!(
+                moderator_loader,
+                "moderator-automatic-block",
+                user = applicant,
+                sources = sources
+            ),
+        ),
+        ScammerAction::Ban => {
+            if let Err(err) = bot
+                .ban_chat_member(ChatId(config.primary_chat_id), user.id)
+                .await
+            {
+                warn!("failed to ban {} from the primary chat: {err}", user.id);
+            }
+
+            (
+                ReviewAction::Block,
+                fl!(
+                    moderator_loader,
+                    "moderator-automatic-ban",
+                    user = applicant,
+                    sources = sources
+                ),
+            )
+        }
+    };
+
+    if join_request {
+        decline_join_request(bot, config, user.id).await;
+    }
+
+    let send_result = if review_action == ReviewAction::Deny {
+        // The applicant is not told about the denial, so they have no reason to try again.
+        dialogue.exit().await?;
+        SendResult::Withheld
+    } else {
+        dialogue.update(State::Blocked).await?;
+        let loader = loader_from_message(msg);
+        check_send_result(bot.send_message(msg.chat.id, fl!(loader, "blocked")).await)?
+    };
+
+    if let Some(note) = send_result_note(&moderator_loader, &send_result) {
+        summary.push_str(&format!("\n\n{note}"));
+    }
+
+    let request_id = new_request_id();
+
+    // The request is stored as decided right away, so that it never shows up as pending, even
+    // if the summary cannot be posted.
+    let request = RequestRecord {
+        request_id: request_id.clone(),
+        chat_id: ChatId(config.primary_chat_id),
+        user_id: user.id,
+        display_name: get_plaintext_display_name(user),
+        username: user.username.clone(),
+        reason: reason.to_string(),
+        locale: locale_from_message(msg).to_string(),
+        join_request,
+        status: RequestStatus::Decided,
+        message_id: None,
+        submitted_at: Utc::now(),
+    };
+    audit_log.record_request(&request).await?;
+
+    let decision = DecisionRecord {
+        request_id: request_id.clone(),
+        user_id: user.id,
+        outcome: Outcome::Review(review_action),
+        // Automatic actions are not attributed to any moderator.
+        reviewer_id: UserId(0),
+        reviewer: fl!(moderator_loader, "moderator-automatic-policy"),
+        send_result: send_result.as_str().to_string(),
+        decided_at: Utc::now(),
+    };
+
+    if let Err(err) = audit_log.record_decision(&decision).await {
+        error!("failed to record automatic decision: {err}");
+    }
+
+    METRICS.record_review_action(review_action);
+
+    let mut summary_message = bot.send_message(ChatId(config.moderator_chat_id), summary);
+
+    if let ReviewAction::Block = review_action {
+        summary_message = summary_message.reply_markup(InlineKeyboardMarkup::new(vec![vec![
+            InlineKeyboardButton::callback(
+                fl!(moderator_loader, "moderator-button-unblock"),
+                ReviewCallback::new(ReviewAction::Unblock, &request_id),
+            ),
+        ]]));
+    }
+
+    let summary_message = summary_message.await?;
+
+    audit_log
+        .set_request_message(&request_id, summary_message.id)
         .await?;
 
-    bot.send_message(msg.chat.id, fl!(loader, "reason-received"))
+    info!(user:debug, action:debug; "Automatic action taken on listed scammer");
+
+    Ok(true)
+}
+
+async fn update_review_message(
+    bot: Bot,
+    audit_log: &AuditLog,
+    message: Message,
+    request_id: Option<&str>,
+    footer: &str,
+    keyboard_markup: Option<InlineKeyboardMarkup>,
+    send_result: SendResult,
+) -> HandlerResult {
+    let (mut text, entities) = match review_message_content(audit_log, request_id, &message).await?
+    {
+        Some(content) => content,
+        None => return Ok(()),
+    };
+
+    text.push_str("\n\n");
+    text.push_str(footer);
+
+    let mut edit_message = bot
+        .edit_message_text(message.chat.id, message.id, &text)
+        .entities(entities);
+
+    if matches!(send_result, SendResult::Sent)
+        && let Some(keyboard_markup) = keyboard_markup
+    {
+        edit_message = edit_message.reply_markup(keyboard_markup);
+    }
+
+    let edited = edit_message.await?;
+
+    if let Some(request_id) = request_id
+        && let Err(err) = audit_log
+            .update_review_message_text(
+                request_id,
+                edited.text().unwrap_or_default(),
+                edited.entities().unwrap_or_default(),
+            )
+            .await
+    {
+        error!("failed to record review message: {err}");
+    }
+
+    Ok(())
+}
+
+/// Appends a note to a review message outside of a callback. Review messages which were not
+/// recorded cannot be edited without their text, so the note is posted as a reply instead.
+async fn annotate_review(
+    bot: &Bot,
+    config: &Config,
+    audit_log: &AuditLog,
+    request_id: &str,
+    note: &str,
+) -> HandlerResult {
+    let Some(message_id) = audit_log
+        .request(request_id)
+        .await?
+        .and_then(|request| request.message_id)
+    else {
+        return Ok(());
+    };
+
+    let moderator_chat_id = ChatId(config.moderator_chat_id);
+
+    let Some(review_message) = audit_log.review_message(request_id).await? else {
+        if let Err(err) = bot
+            .edit_message_reply_markup(moderator_chat_id, message_id)
+            .await
+        {
+            warn!("failed to remove review buttons: {err}");
+        }
+
+        bot.send_message(moderator_chat_id, note)
+            .reply_parameters(ReplyParameters::new(message_id).allow_sending_without_reply())
+            .await?;
+        return Ok(());
+    };
+
+    let edited = bot
+        .edit_message_text(
+            moderator_chat_id,
+            message_id,
+            format!("{}\n\n{note}", review_message.text),
+        )
+        .entities(review_message.entities)
         .await?;
-    dialogue
-        .update(State::AwaitApproval {
-            message_id: moderator_message.id,
-        })
+
+    audit_log
+        .update_review_message_text(
+            request_id,
+            edited.text().unwrap_or_default(),
+            edited.entities().unwrap_or_default(),
+        )
         .await?;
 
-    info!(user:debug; "Join reason received");
+    Ok(())
+}
+
+const INVITE_LINK_MAX_MEMBER_LIMIT: u32 = 99_999;
+const INVITE_LINK_NAME_LENGTH: usize = 32;
+
+/// Names an invite link after who it was issued for and by, within Telegram's length limit.
+fn invite_link_name(applicant: &str, reviewers: &str) -> String {
+    let name = format!("{applicant} via {reviewers}");
+
+    if name.chars().count() <= INVITE_LINK_NAME_LENGTH {
+        return name;
+    }
+
+    let mut name: String = name.chars().take(INVITE_LINK_NAME_LENGTH - 1).collect();
+    name.push('…');
+    name
+}
+
+fn review_footer(
+    loader: &FluentLanguageLoader,
+    action: ReviewAction,
+    reviewers: &str,
+    reason: Option<&str>,
+    send_result: &SendResult,
+) -> String {
+    let mut footer = decided_by(loader, action, reviewers);
+
+    if let Some(reason) = reason {
+        footer.push_str(&format!(
+            "\n{}",
+            fl!(loader, "moderator-decision-reason", reason = reason)
+        ));
+    }
+
+    if let Some(note) = send_result_note(loader, send_result) {
+        footer.push_str(&format!("\n\n{note}"));
+    }
+
+    footer
+}
+
+fn decided_by(loader: &FluentLanguageLoader, action: ReviewAction, reviewers: &str) -> String {
+    fl!(
+        loader,
+        "moderator-decided-by",
+        action = review_action_label(loader, action),
+        reviewers = reviewers
+    )
+}
+
+fn send_result_note(loader: &FluentLanguageLoader, send_result: &SendResult) -> Option<String> {
+    match send_result {
+        SendResult::Sent | SendResult::Withheld => None,
+        SendResult::BotBlocked => Some(fl!(loader, "moderator-bot-blocked")),
+        SendResult::UserDeactivated => Some(fl!(loader, "moderator-user-deactivated")),
+    }
+}
+
+fn review_action_label(loader: &FluentLanguageLoader, action: ReviewAction) -> String {
+    match action {
+        ReviewAction::Approve => fl!(loader, "moderator-action-approve"),
+        ReviewAction::UnbanAndApprove => fl!(loader, "moderator-action-unban-approve"),
+        ReviewAction::Deny => fl!(loader, "moderator-action-deny"),
+        ReviewAction::Block => fl!(loader, "moderator-action-block"),
+        ReviewAction::BlockAndReport => fl!(loader, "moderator-action-block-report"),
+        ReviewAction::Unblock => fl!(loader, "moderator-action-unblock"),
+        ReviewAction::RequestContact => fl!(loader, "moderator-action-request-contact"),
+        ReviewAction::Undo => fl!(loader, "moderator-action-undo"),
+    }
+}
+
+fn outcome_label(loader: &FluentLanguageLoader, outcome: Outcome) -> String {
+    match outcome {
+        Outcome::Review(action) => review_action_label(loader, action),
+        Outcome::Expired => fl!(loader, "moderator-outcome-expired"),
+        Outcome::Unknown => fl!(loader, "moderator-outcome-unknown"),
+    }
+}
+
+fn tally_header(loader: &FluentLanguageLoader) -> String {
+    format!("\n\n{}", fl!(loader, "moderator-votes"))
+}
 
-    Ok(())
+/// Returns the text and entities of a review message as recorded, which never includes the vote
+/// tally, or those of the message itself for reviews which were not recorded.
+async fn review_message_content(
+    audit_log: &AuditLog,
+    request_id: Option<&str>,
+    message: &Message,
+) -> Result<Option<(String, Vec<MessageEntity>)>, sqlx::Error> {
+    if let Some(request_id) = request_id
+        && let Some(review_message) = audit_log.review_message(request_id).await?
+    {
+        return Ok(Some((review_message.text, review_message.entities)));
+    }
+
+    Ok(message.text().map(|text| {
+        (
+            text.to_string(),
+            message.entities().unwrap_or_default().to_vec(),
+        )
+    }))
 }
 
-async fn update_review_message(
-    bot: Bot,
-    message: Message,
-    chat_id: ChatId,
-    action: ReviewAction,
-    reviewer: &User,
-    keyboard_markup: Option<InlineKeyboardMarkup>,
-    send_result: SendResult,
-) -> HandlerResult {
-    let mut text = match message.text() {
-        Some(text) => text.to_string(),
-        None => return Ok(()),
+fn tally_text(config: &Config, votes: &[Vote]) -> String {
+    let voters = |approve: bool| {
+        votes
+            .iter()
+            .filter(|vote| vote.approve == approve)
+            .map(|vote| vote.display_name.as_str())
+            .collect::<Vec<_>>()
     };
+    let approvals = voters(true);
+    let denials = voters(false);
+
+    let loader = config.moderator_loader();
+
+    format!(
+        "{}\n{}\n{}",
+        tally_header(&loader),
+        fl!(
+            loader,
+            "moderator-approvals",
+            count = approvals.len(),
+            quorum = config.approval_quorum,
+            voters = approvals.join(", ")
+        ),
+        fl!(
+            loader,
+            "moderator-denials",
+            count = denials.len(),
+            quorum = config.denial_quorum,
+            voters = denials.join(", ")
+        ),
+    )
+}
 
-    let entities = match message.entities() {
-        Some(entities) => entities.to_vec(),
-        None => return Ok(()),
+async fn update_review_tally(
+    bot: &Bot,
+    (config, audit_log): (&Config, &AuditLog),
+    message: &Message,
+    request_id: &str,
+    votes: &[Vote],
+) -> HandlerResult {
+    // The tally is rebuilt from the recorded review each time, as the text of the message already
+    // contains the previous one.
+    let Some(review_message) = audit_log.review_message(request_id).await? else {
+        return Ok(());
     };
 
-    text.push_str(&format!(
-        "\n\n{} by {}",
-        match action {
-            ReviewAction::Approve => "Approved",
-            ReviewAction::UnbanAndApprove => "Unbanned & Approved",
-            ReviewAction::Deny => "Denied",
-            ReviewAction::Block => "Blocked",
-            ReviewAction::Unblock => "Unblocked",
-            ReviewAction::RequestContact => "Contact requested",
-        },
-        get_plaintext_display_name(reviewer),
-    ));
-
-    match send_result {
-        SendResult::Sent => {}
-        SendResult::BotBlocked => text.push_str("\n\nUser has blocked this bot"),
-        SendResult::UserDeactivated => text.push_str("\n\nUser has a deactivated account"),
-    }
+    let mut text = review_message.text;
+    let entities = review_message.entities;
+    text.push_str(&tally_text(config, votes));
 
     let mut edit_message = bot
-        .edit_message_text(chat_id, message.id, &text)
+        .edit_message_text(message.chat.id, message.id, text)
         .entities(entities);
 
-    if matches!(send_result, SendResult::Sent)
-        && let Some(keyboard_markup) = keyboard_markup
-    {
-        edit_message = edit_message.reply_markup(keyboard_markup);
+    if let Some(keyboard_markup) = message.reply_markup() {
+        edit_message = edit_message.reply_markup(keyboard_markup.clone());
     }
 
-    edit_message.await?;
-
-    Ok(())
+    match edit_message.await {
+        Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => Ok(()),
+        Err(error) => Err(error.into()),
+    }
 }
 
 enum SendResult {
     Sent,
     BotBlocked,
     UserDeactivated,
+    /// The applicant was deliberately not notified.
+    Withheld,
 }
 
-fn check_send_result(result: Result<Message, RequestError>) -> Result<SendResult, RequestError> {
-    match result {
-        Ok(_) => Ok(SendResult::Sent),
-        Err(RequestError::Api(ApiError::BotBlocked)) => Ok(SendResult::BotBlocked),
-        Err(RequestError::Api(ApiError::UserDeactivated)) => Ok(SendResult::UserDeactivated),
-        Err(error) => Err(error),
+impl SendResult {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SendResult::Sent => "sent",
+            SendResult::BotBlocked => "bot blocked",
+            SendResult::UserDeactivated => "user deactivated",
+            SendResult::Withheld => "withheld",
+        }
     }
 }
 
+fn check_send_result(result: Result<Message, RequestError>) -> Result<SendResult, RequestError> {
+    let send_result = match result {
+        Ok(_) => {
+            METRICS.record_telegram_success();
+            SendResult::Sent
+        }
+        Err(RequestError::Api(ApiError::BotBlocked)) => SendResult::BotBlocked,
+        Err(RequestError::Api(ApiError::UserDeactivated)) => SendResult::UserDeactivated,
+        Err(error) => return Err(error),
+    };
+
+    METRICS.record_send_result(send_result.as_str());
+    Ok(send_result)
+}
+
 async fn review(
     bot: Bot,
     query: CallbackQuery,
     storage: JoinStorage,
     config: Arc<Config>,
+    audit_log: Arc<AuditLog>,
+    banlist: Arc<Banlist>,
+    locks: Arc<RequestLocks>,
 ) -> HandlerResult {
     let data = match query.data {
         Some(data) => data,
         None => return Ok(()),
     };
 
-    let review: Review = match data.try_into() {
-        Ok(review) => review,
+    let callback: ReviewCallback = match data.try_into() {
+        Ok(callback) => callback,
         Err(error) => {
-            error!(error:err; "Failed to parse review");
+            warn!(error:err; "Failed to parse review");
+
+            let loader = config.moderator_loader();
+            let text = match error {
+                TryFromError::UnsupportedVersion(_) => fl!(loader, "moderator-buttons-outdated"),
+                _ => fl!(loader, "moderator-buttons-invalid"),
+            };
+
+            bot.answer_callback_query(query.id)
+                .text(text)
+                .show_alert(true)
+                .await?;
             return Ok(());
         }
     };
@@ -562,50 +2451,594 @@ async fn review(
         }
     };
 
+    // Concurrent clicks are handled one after the other, so only the first one decides.
+    let _guard = locks.lock(&callback.request_id).await;
+
+    let Some(request) = audit_log.request(&callback.request_id).await? else {
+        bot.answer_callback_query(query.id)
+            .text(fl!(config.moderator_loader(), "moderator-request-unknown"))
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    };
+
+    if let Some(text) = prior_decision(&config, &audit_log, &request, callback.action).await? {
+        bot.answer_callback_query(query.id)
+            .text(text)
+            .show_alert(true)
+            .await?;
+        return Ok(());
+    }
+
+    if let ReviewAction::Undo = callback.action {
+        return undo(
+            &bot,
+            &storage,
+            &config,
+            &audit_log,
+            &banlist,
+            request,
+            (query.id, query.from, message),
+        )
+        .await;
+    }
+
+    let mut review = review_from_request(callback.action, &request);
+
+    if let Some(index) = callback.reason {
+        review = review.with_reason(DenyReason::Preset(index));
+    }
+
     info!(review:debug; "Received review");
     bot.answer_callback_query(query.id).await?;
 
+    if let ReviewAction::BlockAndReport = review.action
+        && let Err(err) = banlist
+            .add(BanlistEntry {
+                user_id: review.user_id,
+                reason: None,
+                added_by: Some(get_plaintext_display_name(&query.from)),
+                added_at: Utc::now(),
+            })
+            .await
+    {
+        error!("failed to add {} to the banlist: {err}", review.user_id);
+    }
+
+    let chat_id = message.chat().id;
+    let message_id = message.id();
+
+    decide(
+        &bot,
+        &storage,
+        &config,
+        &audit_log,
+        review,
+        &query.from,
+        ReviewOrigin::Button(message),
+    )
+    .await?;
+
+    if let Some(window) = config.undo_window()
+        && is_undoable(callback.action)
+    {
+        tokio::spawn(remove_undo_button(
+            bot,
+            (config, audit_log),
+            locks,
+            (callback.request_id, chat_id, message_id),
+            window,
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replaces the buttons of a decided review with the follow-up actions once it can no longer be
+/// undone.
+async fn remove_undo_button(
+    bot: Bot,
+    (config, audit_log): (Arc<Config>, Arc<AuditLog>),
+    locks: Arc<RequestLocks>,
+    (request_id, chat_id, message_id): (String, ChatId, MessageId),
+    window: TimeDelta,
+) {
+    tokio::time::sleep(window.to_std().unwrap_or_default()).await;
+    let _guard = locks.lock(&request_id).await;
+
+    let decision = match audit_log.decisions(&request_id).await {
+        Ok(mut decisions) => decisions.pop(),
+        Err(err) => {
+            error!("failed to load decisions of request {request_id}: {err}");
+            return;
+        }
+    };
+
+    // Votes below the quorum and later decisions, which have their own window, are left alone.
+    let Some(DecisionRecord {
+        outcome: Outcome::Review(action),
+        decided_at,
+        ..
+    }) = decision
+    else {
+        return;
+    };
+
+    if !is_undoable(action) || Utc::now() - decided_at < window {
+        return;
+    }
+
+    let mut edit_message = bot.edit_message_reply_markup(chat_id, message_id);
+
+    if let Some(keyboard_markup) =
+        follow_up_keyboard(&config.moderator_loader(), action, &request_id, false)
+    {
+        edit_message = edit_message.reply_markup(keyboard_markup);
+    }
+
+    match edit_message.await {
+        Ok(_) | Err(RequestError::Api(ApiError::MessageNotModified)) => {}
+        Err(err) => warn!("failed to remove undo button of request {request_id}: {err}"),
+    }
+}
+
+/// Reverts the latest decision on a request within the undo window, so that it is pending again.
+async fn undo(
+    bot: &Bot,
+    storage: &JoinStorage,
+    config: &Config,
+    audit_log: &AuditLog,
+    banlist: &Banlist,
+    request: RequestRecord,
+    (query_id, reviewer, message): (CallbackQueryId, User, MaybeInaccessibleMessage),
+) -> HandlerResult {
+    let decision = audit_log.decisions(&request.request_id).await?.pop();
+    let action = match (decision, config.undo_window()) {
+        (
+            Some(DecisionRecord {
+                outcome: Outcome::Review(action),
+                decided_at,
+                ..
+            }),
+            Some(window),
+        ) if is_undoable(action) && Utc::now() - decided_at <= window => action,
+        _ => {
+            bot.answer_callback_query(query_id)
+                .text(fl!(config.moderator_loader(), "moderator-undo-expired"))
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    if action.vote() == Some(true) {
+        let has_unused_link = audit_log
+            .unused_invite_links(UnusedInviteLinks::User(request.chat_id, request.user_id))
+            .await?
+            .iter()
+            .any(|link| link.request_id == request.request_id);
+
+        if request.join_request || !has_unused_link {
+            bot.answer_callback_query(query_id)
+                .text(fl!(config.moderator_loader(), "moderator-undo-joined"))
+                .show_alert(true)
+                .await?;
+            return Ok(());
+        }
+
+        revoke_invite_links(
+            bot,
+            audit_log,
+            UnusedInviteLinks::User(request.chat_id, request.user_id),
+        )
+        .await?;
+    }
+
+    if let ReviewAction::BlockAndReport = action
+        && let Err(err) = banlist.remove(request.user_id).await
+    {
+        error!(
+            "failed to remove {} from the banlist: {err}",
+            request.user_id
+        );
+    }
+
+    bot.answer_callback_query(query_id).await?;
+
+    let review = review_from_request(ReviewAction::Undo, &request);
+    let loader = LANGUAGE_LOADER.select_languages_negotiate(
+        slice::from_ref(&review.locale),
+        NegotiationStrategy::Filtering,
+    );
+
+    // A contact request leaves the join request open, while any other decision declined it.
+    storage
+        .clone()
+        .update_dialogue(
+            review.chat_id,
+            State::AwaitApproval {
+                message_id: request.message_id.unwrap_or(message.id()),
+                join_request: request.join_request && action == ReviewAction::RequestContact,
+                votes: Vec::new(),
+                reminded_at: None,
+            },
+        )
+        .await?;
+
+    let send_result = check_send_result(
+        bot.send_message(review.chat_id, fl!(loader, "decision-undone"))
+            .await,
+    )?;
+
+    let reviewer_name = get_plaintext_display_name(&reviewer);
+    let record = DecisionRecord {
+        request_id: request.request_id.clone(),
+        user_id: request.user_id,
+        outcome: Outcome::Review(ReviewAction::Undo),
+        reviewer_id: reviewer.id,
+        reviewer: reviewer_name.clone(),
+        send_result: send_result.as_str().to_string(),
+        decided_at: Utc::now(),
+    };
+
+    if let Err(err) = audit_log.record_decision(&record).await {
+        error!("failed to record undo: {err}");
+    }
+
+    METRICS.record_review_action(ReviewAction::Undo);
+
+    let MaybeInaccessibleMessage::Regular(message) = message else {
+        warn!("message is inaccessible, skipping update");
+        return Ok(());
+    };
+
+    let moderator_loader = config.moderator_loader();
+
+    let Some((mut text, entities)) =
+        review_message_content(audit_log, Some(&request.request_id), &message).await?
+    else {
+        return Ok(());
+    };
+
+    text.push_str(&format!(
+        "\n{}",
+        decided_by(&moderator_loader, ReviewAction::Undo, &reviewer_name)
+    ));
+
+    if let Some(note) = send_result_note(&moderator_loader, &send_result) {
+        text.push_str(&format!("\n\n{note}"));
+    }
+
+    let is_banned = is_banned_from_primary_chat(bot, config, request.user_id).await?;
+    let edited = bot
+        .edit_message_text(message.chat.id, message.id, &text)
+        .entities(entities)
+        .reply_markup(review_keyboard(config, &request.request_id, is_banned))
+        .await?;
+
+    if let Err(err) = audit_log
+        .update_review_message_text(
+            &request.request_id,
+            edited.text().unwrap_or_default(),
+            edited.entities().unwrap_or_default(),
+        )
+        .await
+    {
+        error!("failed to record review message: {err}");
+    }
+
+    info!(review:debug; "Decision undone");
+
+    Ok(())
+}
+
+/// Describes who already decided on a request, unless the action follows up on that decision.
+async fn prior_decision(
+    config: &Config,
+    audit_log: &AuditLog,
+    request: &RequestRecord,
+    action: ReviewAction,
+) -> Result<Option<String>, sqlx::Error> {
+    if request.status == RequestStatus::Pending {
+        return Ok(None);
+    }
+
+    let Some(decision) = audit_log.decisions(&request.request_id).await?.pop() else {
+        return Ok(None);
+    };
+
+    // Decisions replace the review buttons with the follow-up actions they allow.
+    let is_follow_up = matches!(
+        (decision.outcome, action),
+        (
+            Outcome::Review(ReviewAction::Block | ReviewAction::BlockAndReport),
+            ReviewAction::Unblock
+        ) | (
+            Outcome::Review(ReviewAction::RequestContact),
+            ReviewAction::Block
+        ) | (_, ReviewAction::Undo)
+    );
+
+    if is_follow_up {
+        return Ok(None);
+    }
+
+    let loader = config.moderator_loader();
+
+    Ok(Some(match decision.outcome {
+        Outcome::Expired => fl!(loader, "moderator-already-expired"),
+        outcome => fl!(
+            loader,
+            "moderator-already-decided",
+            action = outcome_label(&loader, outcome),
+            reviewers = decision.reviewer
+        ),
+    }))
+}
+
+/// Creates a review of a stored request, which messages the applicant in their own language.
+fn review_from_request(action: ReviewAction, request: &RequestRecord) -> Review {
+    let locale = request
+        .locale
+        .parse()
+        .unwrap_or_else(|_| LANGUAGE_LOADER.fallback_language().clone());
+
+    // The applicant's private chat shares its ID with the user.
+    Review::new(
+        action,
+        ChatId(request.user_id.0 as i64),
+        request.user_id,
+        locale,
+    )
+    .with_request_id(request.request_id.clone())
+}
+
+/// Where a review decision was made.
+enum ReviewOrigin {
+    /// A button on the review message was pressed.
+    Button(MaybeInaccessibleMessage),
+    /// A moderator command with the given message ID was sent in the moderator chat.
+    Command(MessageId),
+}
+
+async fn decide(
+    bot: &Bot,
+    storage: &JoinStorage,
+    config: &Config,
+    audit_log: &AuditLog,
+    review: Review,
+    reviewer: &User,
+    origin: ReviewOrigin,
+) -> HandlerResult {
     let loader = LANGUAGE_LOADER.select_languages_negotiate(
         slice::from_ref(&review.locale),
         NegotiationStrategy::Filtering,
     );
+    let moderator_loader = config.moderator_loader();
+
+    let dialogue_state = storage
+        .clone()
+        .get_dialogue(review.chat_id)
+        .await
+        .ok()
+        .flatten();
+    let join_request = matches!(
+        dialogue_state,
+        Some(State::AwaitApproval {
+            join_request: true,
+            ..
+        })
+    );
+    let pending_message_id = match &dialogue_state {
+        Some(State::AwaitApproval { message_id, .. }) => Some(*message_id),
+        _ => None,
+    };
+    let review_message_id = match &origin {
+        ReviewOrigin::Button(message) => Some(message.id()),
+        ReviewOrigin::Command(_) => pending_message_id,
+    };
+    let mut reviewers = get_plaintext_display_name(reviewer);
+
+    let quorum = match review.action.vote() {
+        Some(true) => config.approval_quorum,
+        Some(false) => config.denial_quorum,
+        None => 1,
+    };
+
+    if let Some(approve) = review.action.vote()
+        && let Some(State::AwaitApproval {
+            message_id,
+            join_request,
+            mut votes,
+            reminded_at,
+        }) = dialogue_state
+        && Some(message_id) == review_message_id
+    {
+        votes.retain(|vote| vote.user_id != reviewer.id);
+        votes.push(Vote {
+            user_id: reviewer.id,
+            display_name: reviewers,
+            approve,
+        });
+
+        let voters: Vec<_> = votes
+            .iter()
+            .filter(|vote| vote.approve == approve)
+            .map(|vote| vote.display_name.clone())
+            .collect();
+
+        if voters.len() < quorum {
+            match &origin {
+                ReviewOrigin::Button(MaybeInaccessibleMessage::Regular(message)) => {
+                    if let Some(request_id) = &review.request_id {
+                        update_review_tally(bot, (config, audit_log), message, request_id, &votes)
+                            .await?;
+                    }
+                }
+                ReviewOrigin::Button(_) => {}
+                ReviewOrigin::Command(_) => {
+                    bot.send_message(
+                        ChatId(config.moderator_chat_id),
+                        format!(
+                            "{}{}",
+                            fl!(moderator_loader, "moderator-vote-recorded"),
+                            tally_text(config, &votes)
+                        ),
+                    )
+                    .reply_parameters(ReplyParameters::new(message_id))
+                    .await?;
+                }
+            }
+
+            storage
+                .clone()
+                .update_dialogue(
+                    review.chat_id,
+                    State::AwaitApproval {
+                        message_id,
+                        join_request,
+                        votes,
+                        reminded_at,
+                    },
+                )
+                .await?;
+
+            info!(review:debug; "Vote recorded");
+            return Ok(());
+        }
+
+        reviewers = voters.join(", ");
+    } else if quorum > 1 {
+        // Without the dialogue, the votes cast so far are unknown and the quorum cannot be
+        // checked.
+        let reply_to = match &origin {
+            ReviewOrigin::Button(message) => message.id(),
+            ReviewOrigin::Command(message_id) => *message_id,
+        };
+
+        bot.send_message(
+            ChatId(config.moderator_chat_id),
+            fl!(moderator_loader, "moderator-votes-lost"),
+        )
+        .reply_parameters(ReplyParameters::new(reply_to))
+        .await?;
+
+        info!(review:debug; "Vote rejected");
+        return Ok(());
+    }
 
-    let mut keyboard_markup = None;
+    let request_id = match &review.request_id {
+        Some(request_id) => Some(request_id.clone()),
+        None => audit_log
+            .latest_request_id(ChatId(config.primary_chat_id), review.user_id)
+            .await
+            .unwrap_or_default(),
+    };
+
+    let mut reason = None;
     let send_result;
 
     match review.action {
         ReviewAction::Approve | ReviewAction::UnbanAndApprove => {
             if let ReviewAction::UnbanAndApprove = review.action {
-                bot.unban_chat_member(review.chat_id, review.user_id)
+                bot.unban_chat_member(ChatId(config.primary_chat_id), review.user_id)
                     .await?;
             }
 
-            let invite_link = bot
-                .create_chat_invite_link(ChatId(config.primary_chat_id))
-                .expire_date(Utc::now().add(TimeDelta::hours(24)))
-                .member_limit(1)
-                .await?;
+            let text = if join_request {
+                bot.approve_chat_join_request(ChatId(config.primary_chat_id), review.user_id)
+                    .await?;
+                fl!(loader, "join-request-approved")
+            } else {
+                let applicant = match &request_id {
+                    Some(request_id) => audit_log.request(request_id).await.unwrap_or_default(),
+                    None => None,
+                }
+                .map_or_else(
+                    || review.user_id.to_string(),
+                    |request| request.display_name,
+                );
+
+                let expires_at =
+                    Utc::now().add(TimeDelta::hours(config.invite_link_lifetime_hours.into()));
+                let invite_link = bot
+                    .create_chat_invite_link(ChatId(config.primary_chat_id))
+                    .name(invite_link_name(&applicant, &reviewers))
+                    .expire_date(expires_at)
+                    .member_limit(config.invite_link_member_limit)
+                    .await?;
 
-            send_result = check_send_result(
-                bot.send_message(
-                    review.chat_id,
-                    fl!(loader, "request-approved", link = invite_link.invite_link),
-                )
-                .await,
-            )?;
+                let record = InviteLinkRecord {
+                    invite_link: invite_link.invite_link.clone(),
+                    request_id: request_id.clone().unwrap_or_default(),
+                    chat_id: ChatId(config.primary_chat_id),
+                    user_id: review.user_id,
+                    expires_at,
+                    joined_at: None,
+                };
+
+                if let Err(err) = audit_log.record_invite_link(&record).await {
+                    error!("failed to record invite link: {err}");
+                }
+
+                fl!(loader, "request-approved", link = invite_link.invite_link)
+            };
+
+            send_result = check_send_result(bot.send_message(review.chat_id, text).await)?;
 
-            let _ = storage.remove_dialogue(review.chat_id).await;
+            let _ = storage.clone().remove_dialogue(review.chat_id).await;
         }
         ReviewAction::Deny => {
-            send_result = check_send_result(
-                bot.send_message(review.chat_id, fl!(loader, "request-denied"))
-                    .await,
-            )?;
+            if join_request {
+                decline_join_request(bot, config, review.user_id).await;
+            }
 
-            let _ = storage.remove_dialogue(review.chat_id).await;
+            // Preset reasons are sent in the applicant's language, while the moderator chat
+            // gets the reason in its own language.
+            let localized_reason =
+                match &review.reason {
+                    Some(DenyReason::Preset(index)) => config
+                        .deny_reasons()
+                        .get(*index as usize)
+                        .map(|message_id| {
+                            reason = Some(moderator_loader.get(message_id));
+                            loader.get(message_id)
+                        }),
+                    Some(DenyReason::Custom(text)) => {
+                        reason = Some(text.clone());
+                        Some(text.clone())
+                    }
+                    None => None,
+                };
+
+            let text = match localized_reason {
+                Some(localized_reason) => {
+                    fl!(loader, "request-denied-reason", reason = localized_reason)
+                }
+                None => fl!(loader, "request-denied"),
+            };
+
+            send_result = check_send_result(bot.send_message(review.chat_id, text).await)?;
+
+            let _ = storage.clone().remove_dialogue(review.chat_id).await;
         }
-        ReviewAction::Block => {
+        ReviewAction::Block | ReviewAction::BlockAndReport => {
+            if join_request {
+                decline_join_request(bot, config, review.user_id).await;
+            }
+
+            if let Err(err) = revoke_invite_links(
+                bot,
+                audit_log,
+                UnusedInviteLinks::User(ChatId(config.primary_chat_id), review.user_id),
+            )
+            .await
+            {
+                error!("failed to revoke invite links of {}: {err}", review.user_id);
+            }
+
             let _ = storage
+                .clone()
                 .update_dialogue(review.chat_id, State::Blocked)
                 .await;
 
@@ -613,21 +3046,9 @@ async fn review(
                 bot.send_message(review.chat_id, fl!(loader, "blocked"))
                     .await,
             )?;
-
-            let keyboard: Vec<Vec<InlineKeyboardButton>> =
-                vec![vec![InlineKeyboardButton::callback(
-                    "Unblock",
-                    Review::new(
-                        ReviewAction::Unblock,
-                        review.chat_id,
-                        review.user_id,
-                        review.locale,
-                    ),
-                )]];
-            keyboard_markup = Some(InlineKeyboardMarkup::new(keyboard));
         }
         ReviewAction::Unblock => {
-            let _ = storage.remove_dialogue(review.chat_id).await;
+            let _ = storage.clone().remove_dialogue(review.chat_id).await;
 
             send_result = check_send_result(
                 bot.send_message(review.chat_id, fl!(loader, "unblocked"))
@@ -635,62 +3056,108 @@ async fn review(
             )?;
         }
         ReviewAction::RequestContact => {
-            println!(
-                "{}",
-                fl!(
-                    loader,
-                    "contact-requested",
-                    moderator = get_markdown_display_name(&query.from)
-                )
-            );
-
             send_result = check_send_result(
                 bot.send_message(
                     review.chat_id,
                     fl!(
                         loader,
                         "contact-requested",
-                        moderator = get_markdown_display_name(&query.from)
+                        moderator = get_markdown_display_name(reviewer)
                     ),
                 )
                 .parse_mode(ParseMode::MarkdownV2)
                 .await,
             )?;
 
-            let _ = storage.remove_dialogue(review.chat_id).await;
-
-            let keyboard: Vec<Vec<InlineKeyboardButton>> =
-                vec![vec![InlineKeyboardButton::callback(
-                    "Block",
-                    Review::new(
-                        ReviewAction::Block,
-                        review.chat_id,
-                        review.user_id,
-                        review.locale,
-                    ),
-                )]];
-            keyboard_markup = Some(InlineKeyboardMarkup::new(keyboard));
+            let _ = storage.clone().remove_dialogue(review.chat_id).await;
+        }
+        ReviewAction::Undo => {
+            // Undoing is handled by `undo()`, as it never comes from a command.
+            return Ok(());
         }
     }
 
-    let message = match message {
-        MaybeInaccessibleMessage::Regular(message) => *message,
-        _ => {
+    // Admitting someone through a join request cannot be taken back, as they are in the chat.
+    // Replies to commands are never edited again, so they would keep an expired undo button.
+    let undoable = config.undo_window().is_some()
+        && is_undoable(review.action)
+        && !(review.action.vote() == Some(true) && join_request)
+        && matches!(origin, ReviewOrigin::Button(_));
+    let keyboard_markup = request_id.as_deref().and_then(|request_id| {
+        follow_up_keyboard(&moderator_loader, review.action, request_id, undoable)
+    });
+
+    let record = DecisionRecord {
+        request_id: request_id.clone().unwrap_or_default(),
+        user_id: review.user_id,
+        outcome: Outcome::Review(review.action),
+        reviewer_id: reviewer.id,
+        reviewer: reviewers.clone(),
+        send_result: send_result.as_str().to_string(),
+        decided_at: Utc::now(),
+    };
+
+    if let Err(err) = audit_log.record_decision(&record).await {
+        error!("failed to record review decision: {err}");
+    }
+
+    METRICS.record_review_action(review.action);
+
+    let footer = review_footer(
+        &moderator_loader,
+        review.action,
+        &reviewers,
+        reason.as_deref(),
+        &send_result,
+    );
+
+    let command_message_id = match origin {
+        ReviewOrigin::Button(MaybeInaccessibleMessage::Regular(message)) => {
+            return update_review_message(
+                bot.clone(),
+                audit_log,
+                *message,
+                request_id.as_deref(),
+                &footer,
+                keyboard_markup,
+                send_result,
+            )
+            .await;
+        }
+        ReviewOrigin::Button(_) => {
             warn!("message is inaccessible, skipping update");
             return Ok(());
         }
+        ReviewOrigin::Command(message_id) => message_id,
     };
 
-    update_review_message(
-        bot,
-        message,
-        ChatId(config.moderator_chat_id),
-        review.action,
-        &query.from,
-        keyboard_markup,
-        send_result,
-    )
-    .await?;
+    // The review message cannot be edited without its original text, so the decision is posted
+    // as a reply to it instead, or to the command if there is no pending review.
+    let reply_to = match pending_message_id {
+        Some(message_id) => {
+            if let Err(err) = bot
+                .edit_message_reply_markup(ChatId(config.moderator_chat_id), message_id)
+                .await
+            {
+                warn!("failed to remove review buttons: {err}");
+            }
+
+            message_id
+        }
+        None => command_message_id,
+    };
+
+    let mut reply = bot
+        .send_message(ChatId(config.moderator_chat_id), footer)
+        .reply_parameters(ReplyParameters::new(reply_to));
+
+    if matches!(send_result, SendResult::Sent)
+        && let Some(keyboard_markup) = keyboard_markup
+    {
+        reply = reply.reply_markup(keyboard_markup);
+    }
+
+    reply.await?;
 
     Ok(())
 }
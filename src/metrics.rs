@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+
+use chrono::{DateTime, Utc};
+
+use crate::review::ReviewAction;
+
+pub static METRICS: Metrics = Metrics::new();
+
+/// Counters exposed in the Prometheus text format, along with the state the health check reports.
+pub struct Metrics {
+    requests_received: AtomicU64,
+    review_actions: [AtomicU64; ReviewAction::ALL.len()],
+    send_results: Mutex<BTreeMap<&'static str, u64>>,
+    scammer_list_hits: Mutex<BTreeMap<String, u64>>,
+    scammer_list_fetch_failures: Mutex<BTreeMap<String, u64>>,
+    pins: AtomicU64,
+    unpins: AtomicU64,
+    dispatching: AtomicBool,
+    last_telegram_success: AtomicI64,
+}
+
+impl Metrics {
+    const fn new() -> Self {
+        Self {
+            requests_received: AtomicU64::new(0),
+            review_actions: [const { AtomicU64::new(0) }; ReviewAction::ALL.len()],
+            send_results: Mutex::new(BTreeMap::new()),
+            scammer_list_hits: Mutex::new(BTreeMap::new()),
+            scammer_list_fetch_failures: Mutex::new(BTreeMap::new()),
+            pins: AtomicU64::new(0),
+            unpins: AtomicU64::new(0),
+            dispatching: AtomicBool::new(false),
+            last_telegram_success: AtomicI64::new(0),
+        }
+    }
+
+    pub fn record_request(&self) {
+        self.requests_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_review_action(&self, action: ReviewAction) {
+        self.review_actions[u8::from(action) as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_send_result(&self, send_result: &'static str) {
+        *self
+            .send_results
+            .lock()
+            .unwrap()
+            .entry(send_result)
+            .or_default() += 1;
+    }
+
+    pub fn record_scammer_list_hit(&self, source: &str) {
+        increment(&self.scammer_list_hits, source);
+    }
+
+    pub fn record_scammer_list_fetch_failure(&self, source: &str) {
+        increment(&self.scammer_list_fetch_failures, source);
+    }
+
+    pub fn record_pin(&self) {
+        self.pins.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_unpin(&self) {
+        self.unpins.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn set_dispatching(&self, dispatching: bool) {
+        self.dispatching.store(dispatching, Ordering::Relaxed);
+    }
+
+    pub fn is_dispatching(&self) -> bool {
+        self.dispatching.load(Ordering::Relaxed)
+    }
+
+    pub fn record_telegram_success(&self) {
+        self.last_telegram_success
+            .store(Utc::now().timestamp(), Ordering::Relaxed);
+    }
+
+    pub fn last_telegram_success(&self) -> Option<DateTime<Utc>> {
+        match self.last_telegram_success.load(Ordering::Relaxed) {
+            0 => None,
+            timestamp => DateTime::from_timestamp(timestamp, 0),
+        }
+    }
+
+    /// Renders all counters in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        header(
+            &mut output,
+            "bouncer_requests_received_total",
+            "Join requests submitted for review.",
+        );
+        writeln!(
+            output,
+            "bouncer_requests_received_total {}",
+            self.requests_received.load(Ordering::Relaxed)
+        )
+        .unwrap();
+
+        header(
+            &mut output,
+            "bouncer_review_actions_total",
+            "Decisions on join requests by action.",
+        );
+
+        for action in ReviewAction::ALL {
+            writeln!(
+                output,
+                "bouncer_review_actions_total{{action=\"{}\"}} {}",
+                action_label(action),
+                self.review_actions[u8::from(action) as usize].load(Ordering::Relaxed)
+            )
+            .unwrap();
+        }
+
+        header(
+            &mut output,
+            "bouncer_send_results_total",
+            "Messages to applicants by delivery result.",
+        );
+
+        for (result, count) in self.send_results.lock().unwrap().iter() {
+            writeln!(
+                output,
+                "bouncer_send_results_total{{result=\"{result}\"}} {count}"
+            )
+            .unwrap();
+        }
+
+        for (name, help, counters) in [
+            (
+                "bouncer_scammer_list_hits_total",
+                "Applicants found on a scammer list, by list.",
+                &self.scammer_list_hits,
+            ),
+            (
+                "bouncer_scammer_list_fetch_failures_total",
+                "Failed scammer list fetches, by list.",
+                &self.scammer_list_fetch_failures,
+            ),
+        ] {
+            header(&mut output, name, help);
+
+            for (source, count) in counters.lock().unwrap().iter() {
+                writeln!(
+                    output,
+                    "{name}{{source=\"{}\"}} {count}",
+                    escape_label(source)
+                )
+                .unwrap();
+            }
+        }
+
+        for (name, help, counter) in [
+            (
+                "bouncer_pins_total",
+                "Channel posts pinned in the primary chat.",
+                &self.pins,
+            ),
+            (
+                "bouncer_unpins_total",
+                "Channel posts unpinned from the primary chat.",
+                &self.unpins,
+            ),
+        ] {
+            header(&mut output, name, help);
+            writeln!(output, "{name} {}", counter.load(Ordering::Relaxed)).unwrap();
+        }
+
+        output
+    }
+}
+
+fn increment(counters: &Mutex<BTreeMap<String, u64>>, key: &str) {
+    *counters.lock().unwrap().entry(key.to_string()).or_default() += 1;
+}
+
+fn header(output: &mut String, name: &str, help: &str) {
+    writeln!(output, "# HELP {name} {help}").unwrap();
+    writeln!(output, "# TYPE {name} counter").unwrap();
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn action_label(action: ReviewAction) -> &'static str {
+    match action {
+        ReviewAction::Approve => "approve",
+        ReviewAction::Deny => "deny",
+        ReviewAction::Block => "block",
+        ReviewAction::Unblock => "unblock",
+        ReviewAction::RequestContact => "request_contact",
+        ReviewAction::UnbanAndApprove => "unban_and_approve",
+        ReviewAction::BlockAndReport => "block_and_report",
+        ReviewAction::Undo => "undo",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_counters() {
+        let metrics = Metrics::new();
+        metrics.record_request();
+        metrics.record_review_action(ReviewAction::Deny);
+        metrics.record_review_action(ReviewAction::Deny);
+        metrics.record_send_result("bot blocked");
+        metrics.record_scammer_list_hit("Say \"hi\"");
+        metrics.record_pin();
+
+        let output = metrics.render();
+
+        assert!(output.contains("bouncer_requests_received_total 1\n"));
+        assert!(output.contains("bouncer_review_actions_total{action=\"deny\"} 2\n"));
+        assert!(output.contains("bouncer_review_actions_total{action=\"undo\"} 0\n"));
+        assert!(output.contains("bouncer_send_results_total{result=\"bot blocked\"} 1\n"));
+        assert!(output.contains("bouncer_scammer_list_hits_total{source=\"Say \\\"hi\\\"\"} 1\n"));
+        assert!(output.contains("bouncer_pins_total 1\n"));
+        assert!(output.contains("# TYPE bouncer_unpins_total counter\n"));
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 use std::fs;
 use std::io;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 use log::error;
 use teloxide::types::MessageId;
@@ -22,9 +22,7 @@ impl PinHistory {
         }
     }
 
-    pub fn load(storage_path: &Path, max: usize) -> io::Result<Self> {
-        let file_path = storage_path.join("pinned_messages.json");
-
+    pub fn load(file_path: PathBuf, max: usize) -> io::Result<Self> {
         let entries: VecDeque<MessageId> = match fs::read(&file_path) {
             Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::other)?,
             Err(err) if err.kind() == io::ErrorKind::NotFound => VecDeque::new(),
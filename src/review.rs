@@ -1,11 +1,15 @@
 use base64::prelude::BASE64_STANDARD_NO_PAD;
 use base64::{DecodeError, Engine};
 use i18n_embed::unic_langid::LanguageIdentifier;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Write;
 use std::str::Utf8Error;
+use std::sync::{Arc, Mutex, Weak};
 use teloxide::prelude::ChatId;
 use teloxide::types::UserId;
 use thiserror::Error;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
@@ -16,6 +20,31 @@ pub enum ReviewAction {
     Unblock = 3,
     RequestContact = 4,
     UnbanAndApprove = 5,
+    BlockAndReport = 6,
+    Undo = 7,
+}
+
+impl ReviewAction {
+    pub const ALL: [ReviewAction; 8] = [
+        ReviewAction::Approve,
+        ReviewAction::Deny,
+        ReviewAction::Block,
+        ReviewAction::Unblock,
+        ReviewAction::RequestContact,
+        ReviewAction::UnbanAndApprove,
+        ReviewAction::BlockAndReport,
+        ReviewAction::Undo,
+    ];
+
+    /// Returns whether the action is a vote in favour of or against the applicant, or `None` if
+    /// it is not subject to a quorum.
+    pub fn vote(self) -> Option<bool> {
+        match self {
+            ReviewAction::Approve | ReviewAction::UnbanAndApprove => Some(true),
+            ReviewAction::Deny => Some(false),
+            _ => None,
+        }
+    }
 }
 
 impl From<ReviewAction> for u8 {
@@ -37,17 +66,39 @@ impl TryFrom<u8> for ReviewAction {
             2 => Ok(ReviewAction::Block),
             3 => Ok(ReviewAction::Unblock),
             4 => Ok(ReviewAction::RequestContact),
+            5 => Ok(ReviewAction::UnbanAndApprove),
+            6 => Ok(ReviewAction::BlockAndReport),
+            7 => Ok(ReviewAction::Undo),
             _ => Err(InvalidReviewActionError {}),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Vote {
+    pub user_id: UserId,
+    pub display_name: String,
+    pub approve: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DenyReason {
+    /// Index into the configured deny reasons.
+    Preset(u8),
+    /// A reason typed by a moderator, which is never encoded into callback data.
+    Custom(String),
+}
+
+/// A decision on a join request, either from a button or a moderator command.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Review {
     pub action: ReviewAction,
     pub chat_id: ChatId,
     pub user_id: UserId,
+    /// The stored join request the review belongs to, if known.
+    pub request_id: Option<String>,
     pub locale: LanguageIdentifier,
+    pub reason: Option<DenyReason>,
 }
 
 impl Review {
@@ -61,21 +112,69 @@ impl Review {
             action,
             chat_id,
             user_id,
+            request_id: None,
             locale,
+            reason: None,
         }
     }
+
+    pub fn with_request_id(mut self, request_id: impl Into<String>) -> Self {
+        self.request_id = Some(request_id.into());
+        self
+    }
+
+    pub fn with_reason(mut self, reason: DenyReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
 }
 
-impl From<Review> for String {
-    fn from(review: Review) -> String {
-        let locale = review.locale.to_string();
+/// Version of the callback data format written by [`ReviewCallback`].
+///
+/// Versioned payloads set the high bit of their first byte, which earlier unversioned payloads
+/// used for the review action and thus never set.
+const FORMAT_VERSION: u8 = 0x80 | 2;
 
-        let mut buffer = Vec::with_capacity(32);
-        buffer.write_all(&[review.action.into()]).unwrap();
-        buffer.write_all(&review.chat_id.0.to_le_bytes()).unwrap();
-        buffer.write_all(&review.user_id.0.to_le_bytes()).unwrap();
-        buffer.write_all(&[locale.len() as u8]).unwrap();
-        buffer.write_all(&locale.into_bytes()).unwrap();
+/// Length of the fixed fields preceding the request ID: version, action and request ID length.
+const HEADER_LENGTH: usize = 3;
+
+/// The callback data of a review button, which only refers to the stored join request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReviewCallback {
+    pub action: ReviewAction,
+    pub request_id: String,
+    /// Index into the configured deny reasons.
+    pub reason: Option<u8>,
+}
+
+impl ReviewCallback {
+    pub fn new(action: ReviewAction, request_id: impl Into<String>) -> Self {
+        Self {
+            action,
+            request_id: request_id.into(),
+            reason: None,
+        }
+    }
+
+    pub fn with_reason(mut self, index: u8) -> Self {
+        self.reason = Some(index);
+        self
+    }
+}
+
+impl From<ReviewCallback> for String {
+    fn from(callback: ReviewCallback) -> String {
+        let mut buffer = Vec::with_capacity(16);
+        buffer.write_all(&[FORMAT_VERSION]).unwrap();
+        buffer.write_all(&[callback.action.into()]).unwrap();
+        buffer
+            .write_all(&[callback.request_id.len() as u8])
+            .unwrap();
+        buffer.write_all(callback.request_id.as_bytes()).unwrap();
+
+        if let Some(index) = callback.reason {
+            buffer.write_all(&[index]).unwrap();
+        }
 
         BASE64_STANDARD_NO_PAD.encode(buffer)
     }
@@ -87,10 +186,12 @@ pub enum TryFromError {
     InvalidBase64,
     #[error("Buffer is too short")]
     TooShort,
+    #[error("Unsupported format version {0:#04x}")]
+    UnsupportedVersion(u8),
     #[error("Invalid review action")]
     InvalidReviewAction,
-    #[error("Invalid locale")]
-    InvalidLocale,
+    #[error("Invalid request ID")]
+    InvalidRequestId,
 }
 
 impl From<InvalidReviewActionError> for TryFromError {
@@ -107,64 +208,169 @@ impl From<DecodeError> for TryFromError {
 
 impl From<Utf8Error> for TryFromError {
     fn from(_error: Utf8Error) -> Self {
-        TryFromError::InvalidLocale
+        TryFromError::InvalidRequestId
     }
 }
 
-impl TryFrom<String> for Review {
+impl TryFrom<String> for ReviewCallback {
     type Error = TryFromError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let buffer = BASE64_STANDARD_NO_PAD.decode(value)?;
 
-        if buffer.len() < 18 {
+        let Some(&version) = buffer.first() else {
+            return Err(TryFromError::TooShort);
+        };
+
+        if version != FORMAT_VERSION {
+            return Err(TryFromError::UnsupportedVersion(version));
+        }
+
+        if buffer.len() < HEADER_LENGTH {
             return Err(TryFromError::TooShort);
         }
 
-        let locale_length = buffer[17] as usize;
+        let request_id_length = buffer[HEADER_LENGTH - 1] as usize;
+        let request_id_end = HEADER_LENGTH + request_id_length;
 
-        if buffer.len() - 17 < locale_length {
+        if buffer.len() < request_id_end {
             return Err(TryFromError::TooShort);
         }
 
         Ok(Self {
-            action: buffer[0].try_into()?,
-            chat_id: ChatId(i64::from_le_bytes(buffer[1..9].try_into().unwrap())),
-            user_id: UserId(u64::from_le_bytes(buffer[9..17].try_into().unwrap())),
-            locale: std::str::from_utf8(&buffer[18..18 + locale_length])?
-                .parse()
-                .unwrap(),
+            action: buffer[1].try_into()?,
+            request_id: std::str::from_utf8(&buffer[HEADER_LENGTH..request_id_end])?.to_string(),
+            reason: buffer.get(request_id_end).copied(),
         })
     }
 }
 
+/// Serializes reviews of the same join request, so that concurrent clicks are handled one after
+/// the other and each sees the outcome of the previous one.
+#[derive(Debug, Default)]
+pub struct RequestLocks {
+    locks: Mutex<HashMap<String, Weak<AsyncMutex<()>>>>,
+}
+
+impl RequestLocks {
+    pub async fn lock(&self, request_id: &str) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut locks = self.locks.lock().unwrap();
+            locks.retain(|_, lock| lock.strong_count() > 0);
+
+            match locks.get(request_id).and_then(Weak::upgrade) {
+                Some(lock) => lock,
+                None => {
+                    let lock = Arc::new(AsyncMutex::new(()));
+                    locks.insert(request_id.to_string(), Arc::downgrade(&lock));
+                    lock
+                }
+            }
+        };
+
+        lock.lock_owned().await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn encodes_to_base64() {
-        let review = Review::new(
-            ReviewAction::Approve,
-            ChatId(123456),
-            UserId(987654),
-            "de-DE".parse().unwrap(),
-        );
-        let result: String = review.into();
+        let callback = ReviewCallback::new(ReviewAction::Approve, "aB3dE5gH");
+        let result: String = callback.into();
 
-        assert_eq!(result, "AUDiAQAAAAAABhIPAAAAAAAFZGUtREU");
+        assert_eq!(result, "ggEIYUIzZEU1Z0g");
     }
 
     #[test]
     fn decodes_from_base64() {
+        let data = "ggEIYUIzZEU1Z0g".to_string();
+        let callback: ReviewCallback = data.try_into().unwrap();
+
+        assert_eq!(callback.action, ReviewAction::Approve);
+        assert_eq!(callback.request_id, "aB3dE5gH");
+        assert_eq!(callback.reason, None);
+    }
+
+    #[test]
+    fn rejects_payloads_of_older_versions() {
+        // Unversioned payload, which started with the review action.
         let data = "AUDiAQAAAAAABhIPAAAAAAAFZGUtREU".to_string();
-        let review: Review = data.try_into().unwrap();
-
-        assert_eq!(review.action, ReviewAction::Approve);
-        assert_eq!(review.chat_id.0, 123456);
-        assert_eq!(
-            review.locale,
-            "de-DE".parse::<LanguageIdentifier>().unwrap()
-        );
+
+        assert!(matches!(
+            ReviewCallback::try_from(data),
+            Err(TryFromError::UnsupportedVersion(1))
+        ));
+
+        // First versioned payload, which carried the applicant and their locale.
+        let data = "gQFA4gEAAAAAAAYSDwAAAAAAKgAAAAVkZS1ERQ".to_string();
+
+        assert!(matches!(
+            ReviewCallback::try_from(data),
+            Err(TryFromError::UnsupportedVersion(0x81))
+        ));
+    }
+
+    #[test]
+    fn rejects_truncated_request_id() {
+        let mut data: String = ReviewCallback::new(ReviewAction::Deny, "aB3dE5gH").into();
+        data.truncate(data.len() - 4);
+
+        assert!(matches!(
+            ReviewCallback::try_from(data),
+            Err(TryFromError::TooShort)
+        ));
+    }
+
+    #[test]
+    fn maps_every_action_code() {
+        for action in ReviewAction::ALL {
+            assert_eq!(ReviewAction::try_from(u8::from(action)).unwrap(), action);
+        }
+    }
+
+    #[tokio::test]
+    async fn locks_each_request_separately() {
+        let locks = RequestLocks::default();
+        let guard = locks.lock("a").await;
+        let wait = std::time::Duration::from_millis(50);
+
+        assert!(tokio::time::timeout(wait, locks.lock("b")).await.is_ok());
+        assert!(tokio::time::timeout(wait, locks.lock("a")).await.is_err());
+
+        drop(guard);
+        assert!(tokio::time::timeout(wait, locks.lock("a")).await.is_ok());
+
+        // Locks nobody holds are pruned.
+        drop(locks.lock("c").await);
+        assert_eq!(locks.locks.lock().unwrap().len(), 1);
+    }
+
+    fn callback_strategy() -> impl Strategy<Value = ReviewCallback> {
+        (
+            prop::sample::select(ReviewAction::ALL.to_vec()),
+            "[0-9A-Za-z]{1,16}",
+            prop::option::of(any::<u8>()),
+        )
+            .prop_map(|(action, request_id, reason)| ReviewCallback {
+                action,
+                request_id,
+                reason,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn round_trips_callbacks(callback in callback_strategy()) {
+            let data: String = callback.clone().into();
+            let decoded: ReviewCallback = data.clone().try_into().unwrap();
+
+            // Telegram limits callback data to 64 bytes.
+            prop_assert!(data.len() <= 64);
+            prop_assert_eq!(decoded, callback);
+        }
     }
 }
@@ -0,0 +1,30 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use i18n_embed::fluent::FluentLanguageLoader;
+
+pub enum Rules {
+    Disabled,
+    Message(String),
+    Text(String),
+}
+
+impl Rules {
+    pub fn load(storage_path: &Path, file_name: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(storage_path.join(file_name))?;
+        Ok(Self::Text(text.trim().to_string()))
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, Self::Disabled)
+    }
+
+    pub fn text(&self, loader: &FluentLanguageLoader) -> Option<String> {
+        match self {
+            Self::Disabled => None,
+            Self::Message(message_id) => Some(loader.get(message_id)),
+            Self::Text(text) => Some(text.clone()),
+        }
+    }
+}
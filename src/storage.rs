@@ -0,0 +1,51 @@
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+
+use tokio::fs::{self, File};
+use tokio::io::AsyncWriteExt;
+
+/// Replaces a file within the storage path, so that a crash while writing leaves either the
+/// previous or the new contents behind, but never a partial file.
+pub async fn write_atomically(file_path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temp_file_name = OsString::from(".");
+    temp_file_name.push(file_path.file_name().unwrap_or_default());
+    temp_file_name.push(".tmp");
+    let temp_file_path = file_path.with_file_name(temp_file_name);
+
+    let mut temp_file = File::create(&temp_file_path).await?;
+    temp_file.write_all(bytes).await?;
+    temp_file.sync_all().await?;
+    drop(temp_file);
+
+    fs::rename(&temp_file_path, file_path).await?;
+
+    // The rename itself only survives a crash once the directory entry is on disk as well.
+    #[cfg(unix)]
+    {
+        let directory_path = file_path
+            .parent()
+            .filter(|directory_path| !directory_path.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        File::open(directory_path).await?.sync_all().await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn replaces_files_without_leaving_temporary_files() {
+        let storage_path = tempfile::tempdir().unwrap();
+        let file_path = storage_path.path().join("list.json");
+
+        write_atomically(&file_path, b"[1]").await.unwrap();
+        write_atomically(&file_path, b"[2]").await.unwrap();
+
+        assert_eq!(std::fs::read(&file_path).unwrap(), b"[2]");
+        assert_eq!(std::fs::read_dir(storage_path.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,297 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use fluent_syntax::ast::Entry;
+use i18n_embed::fluent::FluentLanguageLoader;
+use i18n_embed::unic_langid::LanguageIdentifier;
+use i18n_embed::{AssetsMultiplexor, I18nAssets, I18nEmbedError, LanguageLoader};
+use log::info;
+use thiserror::Error;
+
+use crate::Localizations;
+
+/// The directory within `STORAGE_PATH` whose translations take precedence over the embedded ones.
+const OVERRIDES_DIRECTORY: &str = "i18n";
+
+#[derive(Debug, Error)]
+pub enum TranslationsError {
+    #[error("Failed to read {0}: {1}")]
+    Read(PathBuf, io::Error),
+    #[error("Failed to parse {0}")]
+    Parse(PathBuf),
+    #[error("{0} is not named after a language")]
+    Language(PathBuf),
+    #[error("{0}")]
+    Load(#[from] I18nEmbedError),
+}
+
+/// Translations read from the overrides directory, which is laid out like the embedded `i18n`
+/// folder, e.g. `de/telegram_bouncer_bot.ftl`.
+#[derive(Clone, Default)]
+struct Overrides(BTreeMap<String, Vec<u8>>);
+
+impl Overrides {
+    fn read(directory: &Path, file_name: &str) -> Result<Self, TranslationsError> {
+        let read_error = |path: &Path| {
+            let path = path.to_path_buf();
+            move |err| TranslationsError::Read(path, err)
+        };
+
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(read_error(directory)(err)),
+        };
+
+        let mut files = BTreeMap::new();
+
+        for entry in entries {
+            let entry = entry.map_err(read_error(directory))?;
+            let path = entry.path();
+
+            if !entry.file_type().map_err(read_error(&path))?.is_dir() {
+                continue;
+            }
+
+            let language = entry
+                .file_name()
+                .to_str()
+                .filter(|language| language.parse::<LanguageIdentifier>().is_ok())
+                .ok_or_else(|| TranslationsError::Language(path.clone()))?
+                .to_string();
+
+            let file_path = path.join(file_name);
+
+            match fs::read(&file_path) {
+                Ok(contents) => {
+                    files.insert(format!("{language}/{file_name}"), contents);
+                }
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => return Err(read_error(&file_path)(err)),
+            }
+        }
+
+        Ok(Self(files))
+    }
+}
+
+impl I18nAssets for Overrides {
+    fn get_files(&self, file_path: &str) -> Vec<Cow<'_, [u8]>> {
+        self.0
+            .get(file_path)
+            .map(|contents| Cow::Borrowed(contents.as_slice()))
+            .into_iter()
+            .collect()
+    }
+
+    fn filenames_iter(&self) -> Box<dyn Iterator<Item = String> + '_> {
+        Box::new(self.0.keys().cloned())
+    }
+}
+
+/// What the overrides of a language change about the embedded translations.
+#[derive(Debug, PartialEq)]
+pub struct Report {
+    pub language: String,
+    /// Whether the language is not among the embedded ones.
+    pub is_new: bool,
+    pub overridden: Vec<String>,
+    pub added: Vec<String>,
+}
+
+/// The overrides directory within `STORAGE_PATH`, read once so that they can be checked in a
+/// separate loader before they are applied to the one in use.
+pub struct Translations {
+    overrides: Overrides,
+    reports: Vec<Report>,
+}
+
+impl Translations {
+    /// Reads the overrides of the loader's translation files within `storage_path`.
+    pub fn read(
+        loader: &FluentLanguageLoader,
+        storage_path: Option<&Path>,
+    ) -> Result<Self, TranslationsError> {
+        let file_name = format!("{}.ftl", loader.domain());
+        let overrides = match storage_path {
+            Some(storage_path) => {
+                Overrides::read(&storage_path.join(OVERRIDES_DIRECTORY), &file_name)?
+            }
+            None => Overrides::default(),
+        };
+
+        let mut reports = Vec::new();
+
+        for (path, contents) in &overrides.0 {
+            let Some((language, _)) = path.split_once('/') else {
+                continue;
+            };
+
+            let override_ids = message_ids(contents).ok_or_else(|| {
+                TranslationsError::Parse(Path::new(OVERRIDES_DIRECTORY).join(path))
+            })?;
+            let embedded = Localizations::get(path);
+            let embedded_ids = embedded
+                .as_ref()
+                .and_then(|file| message_ids(&file.data))
+                .unwrap_or_default();
+
+            let (overridden, added) = override_ids
+                .into_iter()
+                .partition(|id| embedded_ids.contains(id));
+
+            reports.push(Report {
+                language: language.to_string(),
+                is_new: embedded.is_none(),
+                overridden,
+                added,
+            });
+        }
+
+        Ok(Self { overrides, reports })
+    }
+
+    pub fn reports(&self) -> &[Report] {
+        &self.reports
+    }
+
+    /// Loads the embedded translations into the loader, preceded by the overrides. Messages
+    /// missing from an override are taken from the embedded translations.
+    pub fn apply(&self, loader: &FluentLanguageLoader) -> Result<(), TranslationsError> {
+        loader.load_available_languages(&AssetsMultiplexor::new([
+            Box::new(self.overrides.clone()) as Box<dyn I18nAssets + Send + Sync>,
+            Box::new(Localizations),
+        ]))?;
+
+        // Isolation marks around arguments would end up in the middle of Markdown and shift the
+        // entities of moderator messages. Loading replaces the bundles, so this is applied every
+        // time.
+        loader.set_use_isolating(false);
+
+        Ok(())
+    }
+}
+
+fn message_ids(contents: &[u8]) -> Option<BTreeSet<String>> {
+    let source = std::str::from_utf8(contents).ok()?;
+    let resource = fluent_syntax::parser::parse(source).ok()?;
+
+    Some(
+        resource
+            .body
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Message(message) => Some(message.id.name.to_string()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Logs which messages the overrides replace or add, so that typos in their IDs stand out.
+pub fn log_reports(reports: &[Report]) {
+    for report in reports {
+        if report.is_new {
+            info!(
+                "translation overrides add the language {} with {} messages",
+                report.language,
+                report.added.len()
+            );
+            continue;
+        }
+
+        if !report.overridden.is_empty() {
+            info!(
+                "translation overrides for {} replace {}",
+                report.language,
+                report.overridden.join(", ")
+            );
+        }
+
+        if !report.added.is_empty() {
+            info!(
+                "translation overrides for {} add {}",
+                report.language,
+                report.added.join(", ")
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use i18n_embed::fluent::fluent_language_loader;
+
+    use super::*;
+
+    fn write_override(storage_path: &Path, language: &str, contents: &str) {
+        let directory = storage_path.join(OVERRIDES_DIRECTORY).join(language);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("telegram_bouncer_bot.ftl"), contents).unwrap();
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_embedded_translations() {
+        let storage_path = tempfile::tempdir().unwrap();
+        write_override(
+            storage_path.path(),
+            "en",
+            "reason-prompt = Why do you want in?\nquestion-pets = Do you have pets?",
+        );
+        write_override(
+            storage_path.path(),
+            "nl",
+            "reason-prompt = Waarom wil je erbij?",
+        );
+
+        let loader: FluentLanguageLoader = fluent_language_loader!();
+        let translations = Translations::read(&loader, Some(storage_path.path())).unwrap();
+        translations.apply(&loader).unwrap();
+
+        assert_eq!(
+            translations.reports(),
+            [
+                Report {
+                    language: "en".to_string(),
+                    is_new: false,
+                    overridden: vec!["reason-prompt".to_string()],
+                    added: vec!["question-pets".to_string()],
+                },
+                Report {
+                    language: "nl".to_string(),
+                    is_new: true,
+                    overridden: Vec::new(),
+                    added: vec!["reason-prompt".to_string()],
+                },
+            ]
+        );
+
+        let english = loader.select_languages(&["en".parse::<LanguageIdentifier>().unwrap()]);
+        assert_eq!(english.get("reason-prompt"), "Why do you want in?");
+        assert_eq!(english.get("question-pets"), "Do you have pets?");
+
+        let dutch = loader.select_languages(&["nl".parse::<LanguageIdentifier>().unwrap()]);
+        assert_eq!(dutch.get("reason-prompt"), "Waarom wil je erbij?");
+        assert_eq!(
+            dutch.get("reason-received"),
+            english.get("reason-received"),
+            "missing messages fall back to the embedded translations"
+        );
+    }
+
+    #[test]
+    fn rejects_unparsable_overrides() {
+        let storage_path = tempfile::tempdir().unwrap();
+        write_override(storage_path.path(), "de", "reason-prompt = {");
+
+        let loader: FluentLanguageLoader = fluent_language_loader!();
+
+        assert!(matches!(
+            Translations::read(&loader, Some(storage_path.path())),
+            Err(TranslationsError::Parse(_))
+        ));
+    }
+}